@@ -0,0 +1,592 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpStream};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::{task, time};
+
+use crate::error::AppError;
+
+/// Address of the local ADB server.
+pub const ADB_SERVER: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5037);
+
+/// How often an operation is retried after the connection to the device dropped.
+const RECONNECT_ATTEMPTS: usize = 2;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// How long connecting to the ADB server may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a request may stall before it fails, so a hung device does not block the thread
+/// forever.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An Android user (or work profile) as reported by `pm list users`.
+pub struct User {
+    pub id: u32,
+    pub name: String,
+}
+
+/// A third-party app installed on the device as reported by `pm list packages -3`.
+pub struct Package {
+    pub name: String,
+    pub version_code: Option<u64>,
+}
+
+/// A device known to the ADB server as reported by `adb devices -l`.
+#[derive(Clone)]
+pub struct Device {
+    pub serial: String,
+    pub state: String,
+    pub model: Option<String>,
+}
+
+impl Device {
+    /// Whether the device is online and authorized.
+    pub fn is_ready(&self) -> bool {
+        self.state == "device"
+    }
+
+    /// The device if it is ready, otherwise why it is not.
+    pub fn ready(self) -> Result<Self, AppError> {
+        match self.state.as_str() {
+            "device" => Ok(self),
+            "unauthorized" => Err(AppError::DeviceUnauthorized(self.serial)),
+            state => Err(AppError::Adb(format!("{} is {}", self.serial, state))),
+        }
+    }
+}
+
+/// Why none of the devices is ready, pointing out one waiting for its authorization prompt.
+pub fn not_ready(devices: &[Device]) -> AppError {
+    match devices.iter().find(|d| d.state == "unauthorized") {
+        Some(device) => AppError::DeviceUnauthorized(device.serial.clone()),
+        None => AppError::NoDevice,
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.model {
+            Some(model) => write!(f, "{} ({})", self.serial, model),
+            None => write!(f, "{}", self.serial),
+        }
+    }
+}
+
+/// A port mapping set up for the session, like `adb forward` or `adb reverse`.
+#[derive(Deserialize, Clone)]
+pub struct PortMapping {
+    pub direction: Direction,
+    /// Socket on this machine, e.g. `tcp:8080`.
+    pub local: String,
+    /// Socket on the device, e.g. `tcp:8080` or `localabstract:chrome_devtools_remote`.
+    pub remote: String,
+}
+
+#[derive(Deserialize, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Connections to `local` reach `remote` on the device.
+    Forward,
+    /// Connections to `remote` on the device reach `local`.
+    Reverse,
+}
+
+impl fmt::Display for PortMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Forward => write!(f, "forward {} -> {}", self.local, self.remote),
+            Direction::Reverse => write!(f, "reverse {} -> {}", self.remote, self.local),
+        }
+    }
+}
+
+/// Makes sure an ADB server is listening, starting one via `adb start-server` if needed.
+pub fn ensure_server() -> Result<(), AppError> {
+    match TcpStream::connect_timeout(&ADB_SERVER.into(), CONNECT_TIMEOUT) {
+        Ok(_) => Ok(()),
+        Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+            let started = Command::new("adb")
+                .arg("start-server")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success());
+
+            if started {
+                Ok(())
+            } else {
+                Err(AppError::AdbServerNotRunning)
+            }
+        }
+        Err(error) => Err(AppError::Adb(error.to_string())),
+    }
+}
+
+/// Returns the device with the given serial, or the first ready one.
+pub fn select_device(serial: Option<&str>) -> Result<Device, AppError> {
+    ensure_server()?;
+    let devices = devices()?;
+    match serial {
+        Some(serial) => devices
+            .into_iter()
+            .find(|d| d.serial == serial)
+            .ok_or_else(|| AppError::DeviceNotFound(serial.to_string()))?
+            .ready(),
+        None => devices
+            .iter()
+            .find(|d| d.is_ready())
+            .cloned()
+            .ok_or_else(|| not_ready(&devices)),
+    }
+}
+
+/// Checks that the ADB server is reachable and the device is still connected and authorized.
+pub fn check_device(serial: &str) -> Result<Device, AppError> {
+    ensure_server()?;
+    devices()?
+        .into_iter()
+        .find(|d| d.serial == serial)
+        .ok_or_else(|| AppError::DeviceNotFound(serial.to_string()))?
+        .ready()
+}
+
+/// Runs the operation, which has to open its own connection, again when it failed because the
+/// device dropped off, e.g. on a flaky USB hub. `on_retry` is told about each reconnection.
+///
+/// Waiting to reconnect blocks the thread, async code uses [`with_reconnect_async`].
+pub fn with_reconnect<T, E: fmt::Display>(
+    mut on_retry: impl FnMut(String),
+    mut operation: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut attempt = 0;
+    loop {
+        match operation() {
+            Err(error) if reconnects(&error, &mut attempt, &mut on_retry) => {
+                thread::sleep(RECONNECT_DELAY);
+                // the server may have died with the connection
+                let _ = ensure_server();
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Like [`with_reconnect`], for async code: each attempt runs on a blocking thread and the delay
+/// before reconnecting does not hold up the runtime.
+pub async fn with_reconnect_async<T: Send + 'static>(
+    mut on_retry: impl FnMut(String),
+    operation: impl FnOnce() -> io::Result<T> + Clone + Send + 'static,
+) -> io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match task::spawn_blocking(operation.clone())
+            .await
+            .map_err(io::Error::other)?
+        {
+            Err(error) if reconnects(&error, &mut attempt, &mut on_retry) => {
+                time::sleep(RECONNECT_DELAY).await;
+                let _ = task::spawn_blocking(ensure_server).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether to try again after the error, telling `on_retry` about it if so.
+fn reconnects(
+    error: &impl fmt::Display,
+    attempt: &mut usize,
+    on_retry: &mut impl FnMut(String),
+) -> bool {
+    if *attempt >= RECONNECT_ATTEMPTS || !is_disconnect(&error.to_string()) {
+        return false;
+    }
+    *attempt += 1;
+    on_retry(format!(
+        "ADB connection lost ({}), reconnecting {}/{}",
+        error, attempt, RECONNECT_ATTEMPTS
+    ));
+    true
+}
+
+/// Whether the error means the connection to the device is gone rather than the command failed.
+fn is_disconnect(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "connection reset",
+        "connection refused",
+        "connection aborted",
+        "broken pipe",
+        "device offline",
+        "not found",
+        "unexpected end of file",
+        "failed to fill whole buffer",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Runs a shell command on the device and returns its output.
+pub fn shell(serial: Option<&str>, command: &[&str]) -> io::Result<String> {
+    shell_with_timeout(serial, command, IO_TIMEOUT)
+}
+
+/// Runs a shell command that may print nothing for up to `timeout`, like `pm install`.
+pub fn shell_with_timeout(
+    serial: Option<&str>,
+    command: &[&str],
+    timeout: Duration,
+) -> io::Result<String> {
+    let service = format!("shell:{}", command_line(command));
+    let output = device_service(serial, &service, timeout)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Runs a shell command on the device and passes its output line by line to `on_line` until the
+/// command ends or `on_line` returns `false`.
+pub fn shell_lines(
+    serial: Option<&str>,
+    command: &[&str],
+    mut on_line: impl FnMut(String) -> bool,
+) -> io::Result<()> {
+    let mut stream = connect_server(IO_TIMEOUT)?;
+    let transport = match serial {
+        Some(serial) => format!("host:transport:{}", serial),
+        None => "host:transport-any".to_string(),
+    };
+    request(&mut stream, &transport)?;
+    request(&mut stream, &format!("shell:{}", command_line(command)))?;
+    // a command like `logcat` may print nothing for a long time
+    stream.set_read_timeout(None)?;
+
+    for line in BufReader::new(stream).lines() {
+        if !on_line(line?.trim_end().to_string()) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Runs a command on the device and returns its raw output, like `adb exec-out`.
+///
+/// Unlike [`shell`] no pty is involved, so binary output such as a PNG stays intact.
+pub fn exec(serial: Option<&str>, command: &[&str]) -> io::Result<Vec<u8>> {
+    device_service(
+        serial,
+        &format!("exec:{}", command_line(command)),
+        IO_TIMEOUT,
+    )
+}
+
+/// Installs the APK by streaming it into `cmd package install -S`, like `adb install --streaming`,
+/// without pushing it to the device first. Returns the output of the install, which may take up
+/// to `timeout` once the APK is sent.
+pub fn install_streamed(
+    serial: &str,
+    path: &Path,
+    args: &[&str],
+    timeout: Duration,
+) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut stream = connect_server(timeout)?;
+    request(&mut stream, &format!("host:transport:{}", serial))?;
+    let size = size.to_string();
+    let command = [&["cmd", "package", "install", "-S", &size], args].concat();
+    request(&mut stream, &format!("exec:{}", command_line(&command)))?;
+    io::copy(&mut file, &mut stream)?;
+
+    let mut output = String::new();
+    stream.read_to_string(&mut output)?;
+    Ok(output)
+}
+
+/// Joins the arguments into a command for the shell of the device, each quoted as a single word, so
+/// e.g. a package name containing `;` can not run commands of its own.
+fn command_line(command: &[&str]) -> String {
+    command
+        .iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Connects to the device and reads everything the service writes, failing if it stalls for
+/// longer than `timeout`.
+fn device_service(serial: Option<&str>, service: &str, timeout: Duration) -> io::Result<Vec<u8>> {
+    let mut stream = connect_server(timeout)?;
+
+    let transport = match serial {
+        Some(serial) => format!("host:transport:{}", serial),
+        None => "host:transport-any".to_string(),
+    };
+    request(&mut stream, &transport)?;
+    request(&mut stream, service)?;
+
+    let mut output = Vec::new();
+    stream.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Connects the ADB server to a device listening on `host:port`, like `adb connect`.
+pub fn connect(address: &str) -> Result<(), AppError> {
+    let reply = host(&format!("host:connect:{}", address))
+        .map_err(|error| AppError::Connect(address.to_string(), error.to_string()))?;
+    // the server answers OKAY either way and only tells in the message whether it worked
+    if reply.contains("connected to") && !reply.starts_with("failed") {
+        Ok(())
+    } else {
+        Err(AppError::Connect(address.to_string(), reply))
+    }
+}
+
+/// Sets up the port mapping on the device.
+pub fn open_port_mapping(serial: &str, mapping: &PortMapping) -> io::Result<()> {
+    let mut stream = connect_server(IO_TIMEOUT)?;
+    match mapping.direction {
+        Direction::Forward => {
+            let payload = format!(
+                "host-serial:{}:forward:{};{}",
+                serial, mapping.local, mapping.remote
+            );
+            // the first OKAY accepts the request, the second one reports the result
+            request(&mut stream, &payload)?;
+            read_status(&mut stream)
+        }
+        Direction::Reverse => {
+            request(&mut stream, &format!("host:transport:{}", serial))?;
+            let payload = format!("reverse:forward:{};{}", mapping.remote, mapping.local);
+            request(&mut stream, &payload)?;
+            read_status(&mut stream)
+        }
+    }
+}
+
+/// Removes a port mapping set up by [`open_port_mapping`].
+pub fn close_port_mapping(serial: &str, mapping: &PortMapping) -> io::Result<()> {
+    let mut stream = connect_server(IO_TIMEOUT)?;
+    match mapping.direction {
+        Direction::Forward => {
+            let payload = format!("host-serial:{}:killforward:{}", serial, mapping.local);
+            request(&mut stream, &payload)
+        }
+        Direction::Reverse => {
+            request(&mut stream, &format!("host:transport:{}", serial))?;
+            request(
+                &mut stream,
+                &format!("reverse:killforward:{}", mapping.remote),
+            )?;
+            read_status(&mut stream)
+        }
+    }
+}
+
+/// Lists the devices known to the ADB server.
+pub fn devices() -> io::Result<Vec<Device>> {
+    let output = host("host:devices-l")?;
+    Ok(output.lines().filter_map(parse_device).collect())
+}
+
+/// Parses a line like `emulator-5554 device product:sdk_gphone model:sdk_gphone transport_id:1`.
+fn parse_device(line: &str) -> Option<Device> {
+    let mut fields = line.split_whitespace();
+    let serial = fields.next()?.to_string();
+    let state = fields.next()?.to_string();
+    let model = fields
+        .find_map(|field| field.strip_prefix("model:"))
+        .map(str::to_string);
+    Some(Device {
+        serial,
+        state,
+        model,
+    })
+}
+
+/// Lists the users present on the device.
+pub fn list_users(serial: Option<&str>) -> io::Result<Vec<User>> {
+    let output = shell(serial, &["pm", "list", "users"])?;
+    Ok(output.lines().filter_map(parse_user).collect())
+}
+
+/// Parses a line like `UserInfo{10:Work profile:30} running`.
+fn parse_user(line: &str) -> Option<User> {
+    let info = line.trim().strip_prefix("UserInfo{")?;
+    let mut fields = info.split(':');
+    let id = fields.next()?.parse().ok()?;
+    let name = fields.next()?.to_string();
+    Some(User { id, name })
+}
+
+/// Returns the ABIs the device supports, preferred first, e.g. `arm64-v8a, armeabi-v7a`.
+pub fn abis(serial: Option<&str>) -> io::Result<Vec<String>> {
+    let output = shell(serial, &["getprop", "ro.product.cpu.abilist"])?;
+    Ok(output
+        .trim()
+        .split(',')
+        .filter(|abi| !abi.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Returns the SDK level of the device, e.g. `34` for Android 14.
+pub fn sdk(serial: Option<&str>) -> io::Result<Option<u32>> {
+    let output = shell(serial, &["getprop", "ro.build.version.sdk"])?;
+    Ok(output.trim().parse().ok())
+}
+
+/// Lists the third-party packages installed on the device.
+pub fn installed_packages(serial: Option<&str>) -> io::Result<Vec<Package>> {
+    let output = shell(
+        serial,
+        &["pm", "list", "packages", "-3", "--show-versioncode"],
+    )?;
+    let mut packages: Vec<Package> = output.lines().filter_map(parse_package).collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+/// Parses a line like `package:com.example.app versionCode:42`.
+fn parse_package(line: &str) -> Option<Package> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?.strip_prefix("package:")?.to_string();
+    let version_code = fields
+        .find_map(|field| field.strip_prefix("versionCode:"))
+        .and_then(|code| code.parse().ok());
+    Some(Package { name, version_code })
+}
+
+/// Starts the launcher activity of a package.
+pub fn launch(serial: Option<&str>, package: &str) -> io::Result<()> {
+    let launch = [
+        "monkey",
+        "-p",
+        package,
+        "-c",
+        "android.intent.category.LAUNCHER",
+        "1",
+    ];
+    shell(serial, &launch).map(|_| ())
+}
+
+/// Returns the id of the running process of a package.
+pub fn pid(serial: Option<&str>, package: &str) -> io::Result<Option<u32>> {
+    let output = shell(serial, &["pidof", package])?;
+    Ok(output
+        .split_whitespace()
+        .next()
+        .and_then(|pid| pid.parse().ok()))
+}
+
+/// Returns the `versionName` of an installed package.
+pub fn version_name(serial: Option<&str>, package: &str) -> io::Result<Option<String>> {
+    let output = shell(serial, &["dumpsys", "package", package])?;
+    Ok(output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("versionName="))
+        .map(str::to_string))
+}
+
+/// Returns the `versionCode` of an installed package, `None` if it is not installed.
+pub fn version_code(serial: Option<&str>, package: &str) -> io::Result<Option<u64>> {
+    let output = shell(serial, &["dumpsys", "package", package])?;
+    Ok(output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("versionCode="))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|code| code.parse().ok()))
+}
+
+/// Returns the signature hashes of an installed package as printed by `dumpsys package`.
+///
+/// The list is empty if the package is not installed.
+pub fn installed_signatures(serial: Option<&str>, package: &str) -> io::Result<Vec<String>> {
+    let output = shell(serial, &["dumpsys", "package", package])?;
+    Ok(output
+        .lines()
+        .find_map(|line| line.split_once("signatures:[").map(|(_, rest)| rest))
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(list, _)| {
+            list.split(',')
+                .map(|hash| hash.trim().to_string())
+                .filter(|hash| !hash.is_empty())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Sends a request to a host service and returns its reply.
+fn host(payload: &str) -> io::Result<String> {
+    let mut stream = connect_server(IO_TIMEOUT)?;
+    request(&mut stream, payload)?;
+    read_message(&mut stream)
+}
+
+/// Connects to the ADB server, reads and writes on the connection fail after stalling for
+/// `timeout`.
+fn connect_server(timeout: Duration) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect_timeout(&ADB_SERVER.into(), CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+/// Sends a request to the ADB server and waits for its `OKAY`.
+fn request(stream: &mut TcpStream, payload: &str) -> io::Result<()> {
+    stream.write_all(format!("{:04x}{}", payload.len(), payload).as_bytes())?;
+    read_status(stream)
+}
+
+/// Reads an `OKAY`, or the message following a `FAIL`.
+fn read_status(stream: &mut TcpStream) -> io::Result<()> {
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status)?;
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => Err(io::Error::other(read_message(stream)?)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unexpected response from ADB server",
+        )),
+    }
+}
+
+/// Reads a message prefixed with its length as four hex digits.
+fn read_message(stream: &mut TcpStream) -> io::Result<String> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = std::str::from_utf8(&length)
+        .ok()
+        .and_then(|l| usize::from_str_radix(l, 16).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid message length"))?;
+
+    let mut message = vec![0u8; length];
+    stream.read_exact(&mut message)?;
+    Ok(String::from_utf8_lossy(&message).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_quotes_every_argument() {
+        assert_eq!(
+            command_line(&["dumpsys", "package", "com.example.app"]),
+            "'dumpsys' 'package' 'com.example.app'"
+        );
+        assert_eq!(
+            command_line(&["dumpsys", "package", "a;reboot"]),
+            "'dumpsys' 'package' 'a;reboot'"
+        );
+        assert_eq!(
+            command_line(&["pidof", "a'$(reboot)'"]),
+            r"'pidof' 'a'\''$(reboot)'\'''"
+        );
+    }
+}
@@ -0,0 +1,574 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Manifests are a few KB, anything larger is not read into memory.
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+/// Signing blocks hold a few certificates and signatures, a larger size read from the file is
+/// not allocated.
+const MAX_SIGNING_BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+const EOCD_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x05, 0x06];
+const EOCD_MIN_SIZE: usize = 22;
+const APK_SIG_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const V2_BLOCK_ID: u32 = 0x7109_871a;
+const V3_BLOCK_ID: u32 = 0xf053_68c0;
+
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const UTF8_FLAG: u32 = 1 << 8;
+const TYPE_STRING: u8 = 0x03;
+const TYPE_INT_DEC: u8 = 0x10;
+const TYPE_INT_HEX: u8 = 0x11;
+
+/// Signature schemes an APK is signed with.
+#[derive(Default)]
+pub struct SigningSchemes {
+    pub v1: bool,
+    pub v2: bool,
+    pub v3: bool,
+}
+
+impl fmt::Display for SigningSchemes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let schemes: Vec<&str> = [(self.v1, "v1"), (self.v2, "v2"), (self.v3, "v3")]
+            .into_iter()
+            .filter_map(|(present, name)| present.then_some(name))
+            .collect();
+        if schemes.is_empty() {
+            write!(f, "unsigned")
+        } else {
+            write!(f, "{}", schemes.join("+"))
+        }
+    }
+}
+
+/// What is known about an APK before installing it.
+pub struct ApkInfo {
+    pub package: String,
+    pub version_code: Option<u64>,
+    /// `minSdkVersion`, devices with an older SDK refuse the APK.
+    pub min_sdk: Option<u32>,
+    pub target_sdk: Option<u32>,
+    pub schemes: SigningSchemes,
+    /// Hash of the signing certificate as printed by `dumpsys package`, see [`java_hash`].
+    pub certificate_hash: Option<String>,
+}
+
+/// Reads the package name, version code and signing information of an APK.
+///
+/// Only the manifest entry and the signing block are read, so memory use does not grow with the
+/// size of the APK.
+pub fn inspect(path: &Path) -> io::Result<ApkInfo> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let entry = archive.by_name("AndroidManifest.xml")?;
+    if entry.size() > MAX_MANIFEST_SIZE {
+        return Err(invalid("AndroidManifest.xml is too large"));
+    }
+    let mut manifest = Vec::with_capacity(entry.size() as usize);
+    // the declared size can not be trusted, so stop reading after the limit as well
+    entry.take(MAX_MANIFEST_SIZE).read_to_end(&mut manifest)?;
+    let package = manifest_attribute(&manifest, "manifest", "package")
+        .ok_or_else(|| invalid("No package name in AndroidManifest.xml"))?;
+    // it ends up in commands run on the device
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+    if package.is_empty() || !package.chars().all(valid) {
+        return Err(invalid(&format!(
+            "Invalid package name '{}' in AndroidManifest.xml",
+            package
+        )));
+    }
+    let version_code =
+        manifest_attribute(&manifest, "manifest", "versionCode").and_then(|code| code.parse().ok());
+    let sdk = |attribute| {
+        manifest_attribute(&manifest, "uses-sdk", attribute).and_then(|sdk| sdk.parse().ok())
+    };
+    let (min_sdk, target_sdk) = (sdk("minSdkVersion"), sdk("targetSdkVersion"));
+
+    let mut schemes = SigningSchemes {
+        v1: archive.file_names().any(|name| {
+            name.starts_with("META-INF/")
+                && [".RSA", ".DSA", ".EC"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+        }),
+        ..SigningSchemes::default()
+    };
+
+    let mut certificate_hash = None;
+    if let Some(block) = signing_block(&mut File::open(path)?)? {
+        let mut pairs = Reader::new(&block);
+        while let Some(pair) = pairs.u64_prefixed() {
+            let mut pair = Reader::new(pair);
+            let id = pair.u32();
+            match id {
+                Some(V2_BLOCK_ID) => schemes.v2 = true,
+                Some(V3_BLOCK_ID) => schemes.v3 = true,
+                _ => continue,
+            }
+            if certificate_hash.is_none() {
+                certificate_hash = first_certificate(pair.rest())
+                    .map(|certificate| format!("{:x}", java_hash(certificate) as u32));
+            }
+        }
+    }
+
+    Ok(ApkInfo {
+        package,
+        version_code,
+        min_sdk,
+        target_sdk,
+        schemes,
+        certificate_hash,
+    })
+}
+
+/// Same as `java.util.Arrays.hashCode(byte[])`, the hash of a signature `dumpsys package` prints.
+fn java_hash(bytes: &[u8]) -> i32 {
+    bytes.iter().fold(1i32, |hash, &byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as i8 as i32)
+    })
+}
+
+/// Returns the id-value pairs of the APK Signing Block stored right before the central directory.
+fn signing_block(file: &mut File) -> io::Result<Option<Vec<u8>>> {
+    let length = file.metadata()?.len();
+    let search = length.min((EOCD_MIN_SIZE + u16::MAX as usize) as u64);
+    file.seek(SeekFrom::Start(length - search))?;
+    let mut tail = vec![0u8; search as usize];
+    file.read_exact(&mut tail)?;
+
+    let eocd = (0..=tail.len().saturating_sub(EOCD_MIN_SIZE))
+        .rev()
+        .find(|&i| tail[i..i + 4] == EOCD_SIGNATURE)
+        .ok_or_else(|| invalid("No end of central directory found"))?;
+    let central_directory = u32::from_le_bytes([
+        tail[eocd + 16],
+        tail[eocd + 17],
+        tail[eocd + 18],
+        tail[eocd + 19],
+    ]) as u64;
+
+    // the block ends with its size and the magic
+    let Some(footer_start) = central_directory.checked_sub(24) else {
+        return Ok(None);
+    };
+    file.seek(SeekFrom::Start(footer_start))?;
+    let mut footer = [0u8; 24];
+    file.read_exact(&mut footer)?;
+    if &footer[8..] != APK_SIG_BLOCK_MAGIC {
+        return Ok(None);
+    }
+
+    let mut size = [0u8; 8];
+    size.copy_from_slice(&footer[..8]);
+    let size = u64::from_le_bytes(size);
+    let Some(pairs_length) = size.checked_sub(24) else {
+        return Ok(None);
+    };
+    if pairs_length > MAX_SIGNING_BLOCK_SIZE {
+        return Err(invalid("APK Signing Block is too large"));
+    }
+    let Some(start) = central_directory.checked_sub(size) else {
+        return Ok(None);
+    };
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut pairs = vec![0u8; pairs_length as usize];
+    file.read_exact(&mut pairs)?;
+    Ok(Some(pairs))
+}
+
+/// Returns the first certificate of the first signer of a v2 or v3 signature block.
+fn first_certificate(block: &[u8]) -> Option<&[u8]> {
+    let mut signers = Reader::new(Reader::new(block).u32_prefixed()?);
+    let mut signer = Reader::new(signers.u32_prefixed()?);
+    let mut signed_data = Reader::new(signer.u32_prefixed()?);
+    let _digests = signed_data.u32_prefixed()?;
+    let mut certificates = Reader::new(signed_data.u32_prefixed()?);
+    certificates.u32_prefixed()
+}
+
+/// Finds an attribute of the first element with the given name in a binary XML document.
+pub fn manifest_attribute(xml: &[u8], element: &str, attribute: &str) -> Option<String> {
+    let mut strings = Vec::new();
+    // skip the document header
+    let mut offset = 8;
+
+    while offset + 8 <= xml.len() {
+        let chunk_type = u16::from_le_bytes([xml[offset], xml[offset + 1]]);
+        let header_size = u16::from_le_bytes([xml[offset + 2], xml[offset + 3]]) as usize;
+        let chunk_size = read_u32(xml, offset + 4)? as usize;
+        if chunk_size == 0 {
+            return None;
+        }
+        let chunk = xml.get(offset..offset + chunk_size)?;
+
+        match chunk_type {
+            RES_STRING_POOL_TYPE => strings = string_pool(chunk)?,
+            RES_XML_START_ELEMENT_TYPE => {
+                let name = read_u32(chunk, header_size + 4)? as usize;
+                if strings.get(name).map(String::as_str) == Some(element) {
+                    return element_attribute(chunk, header_size, &strings, attribute);
+                }
+            }
+            _ => {}
+        }
+        offset += chunk_size;
+    }
+
+    None
+}
+
+fn element_attribute(
+    chunk: &[u8],
+    header_size: usize,
+    strings: &[String],
+    attribute: &str,
+) -> Option<String> {
+    let extension = header_size;
+    let attribute_start = read_u16(chunk, extension + 8)? as usize;
+    let attribute_size = read_u16(chunk, extension + 10)? as usize;
+    let attribute_count = read_u16(chunk, extension + 12)? as usize;
+
+    (0..attribute_count).find_map(|i| {
+        let at = extension + attribute_start + i * attribute_size;
+        let name = read_u32(chunk, at + 4)? as usize;
+        if strings.get(name)? != attribute {
+            return None;
+        }
+
+        let raw_value = read_u32(chunk, at + 8)?;
+        let data_type = *chunk.get(at + 15)?;
+        let data = read_u32(chunk, at + 16)?;
+        match data_type {
+            _ if raw_value != u32::MAX => strings.get(raw_value as usize).cloned(),
+            TYPE_STRING => strings.get(data as usize).cloned(),
+            TYPE_INT_DEC | TYPE_INT_HEX => Some(data.to_string()),
+            _ => None,
+        }
+    })
+}
+
+fn string_pool(chunk: &[u8]) -> Option<Vec<String>> {
+    let count = read_u32(chunk, 8)? as usize;
+    let flags = read_u32(chunk, 16)?;
+    let strings_start = read_u32(chunk, 20)? as usize;
+    let header_size = read_u16(chunk, 2)? as usize;
+
+    (0..count)
+        .map(|i| {
+            let offset = read_u32(chunk, header_size + i * 4)? as usize;
+            let at = strings_start + offset;
+            if flags & UTF8_FLAG != 0 {
+                // skip the length in UTF-16 code units, then read the length in bytes
+                let at = at + if *chunk.get(at)? & 0x80 != 0 { 2 } else { 1 };
+                let (length, at) = if *chunk.get(at)? & 0x80 != 0 {
+                    (
+                        ((chunk[at] as usize & 0x7f) << 8) | *chunk.get(at + 1)? as usize,
+                        at + 2,
+                    )
+                } else {
+                    (chunk[at] as usize, at + 1)
+                };
+                Some(String::from_utf8_lossy(chunk.get(at..at + length)?).into_owned())
+            } else {
+                let first = read_u16(chunk, at)? as usize;
+                let (length, at) = if first & 0x8000 != 0 {
+                    let second = read_u16(chunk, at + 2)? as usize;
+                    (((first & 0x7fff) << 16) | second, at + 4)
+                } else {
+                    (first, at + 2)
+                };
+                let units: Vec<u16> = chunk
+                    .get(at..at + length * 2)?
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+        })
+        .collect()
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Reads the little-endian, length-prefixed values of the APK Signing Block.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if length > self.data.len() {
+            return None;
+        }
+        let (value, rest) = self.data.split_at(length);
+        self.data = rest;
+        Some(value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take(4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u32_prefixed(&mut self) -> Option<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+
+    fn u64_prefixed(&mut self) -> Option<&'a [u8]> {
+        let mut length = [0u8; 8];
+        length.copy_from_slice(self.take(8)?);
+        self.take(u64::from_le_bytes(length) as usize)
+    }
+
+    fn rest(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::io::Write;
+
+    const PACKAGE: &str = "com.example.large";
+    /// Size of the asset that makes the test APK large.
+    const ASSET_SIZE: usize = 64 * 1024 * 1024;
+    /// What [`inspect`] may allocate at most, well below the size of the test APK.
+    const MAX_ALLOCATED: usize = MAX_MANIFEST_SIZE as usize + 1024 * 1024;
+
+    /// Counts what the current thread allocates, so the tests running alongside do not count.
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATED.with(|allocated| allocated.set(allocated.get() + layout.size()));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    /// Bytes allocated by the current thread while running `f`.
+    fn allocated<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATED.with(Cell::get);
+        let result = f();
+        (result, ALLOCATED.with(Cell::get) - before)
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let table: Vec<u32> = (0..256)
+            .map(|n| {
+                (0..8).fold(n, |crc, _| match crc & 1 {
+                    1 => 0xedb8_8320 ^ (crc >> 1),
+                    _ => crc >> 1,
+                })
+            })
+            .collect();
+        !data.iter().fold(!0, |crc, &byte| {
+            table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+        })
+    }
+
+    /// Binary XML with a `manifest` element that has the `package` attribute.
+    fn manifest(package: &str) -> Vec<u8> {
+        let strings = ["manifest", "package", package];
+        let mut offsets = Vec::new();
+        let mut data = Vec::new();
+        for string in strings {
+            offsets.extend((data.len() as u32).to_le_bytes());
+            let units: Vec<u16> = string.encode_utf16().collect();
+            data.extend((units.len() as u16).to_le_bytes());
+            data.extend(units.iter().flat_map(|unit| unit.to_le_bytes()));
+            data.extend([0, 0]);
+        }
+        let mut pool = Vec::new();
+        let header_size = 28u32;
+        let size = header_size + offsets.len() as u32 + data.len() as u32;
+        pool.extend(RES_STRING_POOL_TYPE.to_le_bytes());
+        pool.extend((header_size as u16).to_le_bytes());
+        pool.extend(size.to_le_bytes());
+        pool.extend((strings.len() as u32).to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend((header_size + offsets.len() as u32).to_le_bytes());
+        pool.extend(0u32.to_le_bytes());
+        pool.extend(offsets);
+        pool.extend(data);
+
+        let mut element = Vec::new();
+        element.extend(RES_XML_START_ELEMENT_TYPE.to_le_bytes());
+        element.extend(16u16.to_le_bytes());
+        element.extend((16u32 + 20 + 20).to_le_bytes());
+        element.extend(1u32.to_le_bytes());
+        element.extend(u32::MAX.to_le_bytes());
+        // namespace, name, start, size and count of the attributes, id, class and style
+        element.extend(u32::MAX.to_le_bytes());
+        element.extend(0u32.to_le_bytes());
+        for value in [20u16, 20, 1, 0, 0, 0] {
+            element.extend(value.to_le_bytes());
+        }
+        // namespace, name and raw value of the attribute, then its typed value
+        element.extend(u32::MAX.to_le_bytes());
+        element.extend(1u32.to_le_bytes());
+        element.extend(2u32.to_le_bytes());
+        element.extend(8u16.to_le_bytes());
+        element.extend([0, TYPE_STRING]);
+        element.extend(2u32.to_le_bytes());
+
+        let mut xml = Vec::new();
+        xml.extend(0x0003u16.to_le_bytes());
+        xml.extend(8u16.to_le_bytes());
+        xml.extend((8 + pool.len() as u32 + element.len() as u32).to_le_bytes());
+        xml.extend(pool);
+        xml.extend(element);
+        xml
+    }
+
+    /// Writes an APK with the stored, uncompressed `entries` and `signing_block` right before
+    /// the central directory.
+    fn write_apk(path: &Path, entries: &[(&str, &[u8])], signing_block: &[u8]) {
+        let mut file = io::BufWriter::new(File::create(path).unwrap());
+        let mut central_directory = Vec::new();
+        let mut offset = 0u32;
+        for (name, data) in entries {
+            let crc = crc32(data);
+            let mut header = Vec::new();
+            header.extend(0x0403_4b50u32.to_le_bytes());
+            // version, flags, method, time and date
+            header.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            header.extend(crc.to_le_bytes());
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((name.len() as u16).to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            file.write_all(&header).unwrap();
+            file.write_all(name.as_bytes()).unwrap();
+            file.write_all(data).unwrap();
+
+            central_directory.extend(0x0201_4b50u32.to_le_bytes());
+            // versions, flags, method, time and date
+            central_directory.extend([20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central_directory.extend(crc.to_le_bytes());
+            central_directory.extend((data.len() as u32).to_le_bytes());
+            central_directory.extend((data.len() as u32).to_le_bytes());
+            central_directory.extend((name.len() as u16).to_le_bytes());
+            // extra and comment length, disk, internal and external attributes
+            central_directory.extend([0; 12]);
+            central_directory.extend(offset.to_le_bytes());
+            central_directory.extend(name.as_bytes());
+            offset += (header.len() + name.len() + data.len()) as u32;
+        }
+        file.write_all(signing_block).unwrap();
+        offset += signing_block.len() as u32;
+
+        let mut eocd = Vec::new();
+        eocd.extend(EOCD_SIGNATURE);
+        eocd.extend([0; 4]);
+        eocd.extend((entries.len() as u16).to_le_bytes());
+        eocd.extend((entries.len() as u16).to_le_bytes());
+        eocd.extend((central_directory.len() as u32).to_le_bytes());
+        eocd.extend(offset.to_le_bytes());
+        eocd.extend(0u16.to_le_bytes());
+        file.write_all(&central_directory).unwrap();
+        file.write_all(&eocd).unwrap();
+    }
+
+    fn temporary(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("github_install-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn inspects_large_apks_in_bounded_memory() {
+        let path = temporary("large.apk");
+        let asset = vec![0x5a; ASSET_SIZE];
+        let manifest = manifest(PACKAGE);
+        write_apk(
+            &path,
+            &[
+                ("AndroidManifest.xml", &manifest),
+                ("assets/large.bin", &asset),
+            ],
+            &[],
+        );
+        drop(asset);
+
+        let (apk, allocated) = allocated(|| inspect(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(apk.unwrap().package, PACKAGE);
+        assert!(allocated < MAX_ALLOCATED, "allocated {} bytes", allocated);
+    }
+
+    #[test]
+    fn refuses_package_names_with_shell_syntax() {
+        let path = temporary("injected.apk");
+        let manifest = manifest("a;reboot");
+        write_apk(&path, &[("AndroidManifest.xml", &manifest)], &[]);
+
+        let apk = inspect(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = apk.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("a;reboot"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_oversized_signing_blocks() {
+        let path = temporary("signing.apk");
+        let asset = vec![0x5a; ASSET_SIZE];
+        let manifest = manifest(PACKAGE);
+        // a footer claiming the block spans nearly the whole APK
+        let mut footer = (ASSET_SIZE as u64).to_le_bytes().to_vec();
+        footer.extend(APK_SIG_BLOCK_MAGIC);
+        write_apk(
+            &path,
+            &[
+                ("AndroidManifest.xml", &manifest),
+                ("assets/large.bin", &asset),
+            ],
+            &footer,
+        );
+        drop(asset);
+
+        let (apk, allocated) = allocated(|| inspect(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(apk.is_err());
+        assert!(allocated < MAX_ALLOCATED, "allocated {} bytes", allocated);
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
+
+use crate::adb::Device;
+use crate::cache::Cache;
+use crate::checksums;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::github::{Asset, GithubClient};
+use crate::install::{self, Replace};
+use crate::report;
+
+/// How many devices are installed to at the same time.
+const MAX_CONCURRENT_INSTALLS: usize = 4;
+
+/// State of the install on a single device.
+#[derive(Clone)]
+pub enum InstallState {
+    /// Waiting for the APK to be downloaded.
+    Downloading,
+    Queued,
+    Installing,
+    Installed,
+    Failed(String),
+}
+
+impl InstallState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, InstallState::Installed | InstallState::Failed(_))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            InstallState::Downloading => "downloading".to_string(),
+            InstallState::Queued => "queued".to_string(),
+            InstallState::Installing => "installing".to_string(),
+            InstallState::Installed => "installed".to_string(),
+            InstallState::Failed(error) => format!("failed: {}", error),
+        }
+    }
+}
+
+/// Progress message sent from an install task to the UI, `device` indexes the broadcast devices.
+pub struct InstallUpdate {
+    pub device: usize,
+    pub state: InstallState,
+    /// SHA-256 of the APK, sent along with the finished state.
+    pub sha256: Option<String>,
+}
+
+/// A device listed in the broadcast panel.
+pub struct DeviceInstall {
+    pub device: Device,
+    pub state: InstallState,
+}
+
+/// Downloads the asset once, checks it against the checksum manifest among `assets` if there is
+/// one, and installs it to all devices, a few at a time, reporting the state of each device over
+/// `updates`.
+pub fn install_all(
+    github: GithubClient,
+    config: Config,
+    asset: Asset,
+    assets: Vec<Asset>,
+    devices: Vec<Device>,
+    updates: UnboundedSender<InstallUpdate>,
+) {
+    tokio::spawn(async move {
+        let send = |device, state| {
+            let _ = updates.send(InstallUpdate {
+                device,
+                state,
+                sha256: None,
+            });
+        };
+
+        let path = async {
+            let hashes = checksums::load(&github, &assets).await?;
+            let path = if config.cache {
+                Cache::new(&config)
+                    .fetch(&github, &asset, |_, _| {})
+                    .await?
+            } else {
+                let path = install::temporary_path(&asset);
+                github
+                    .download_asset(&asset, &path.to_string_lossy(), |_, _| {})
+                    .await?;
+                path
+            };
+            if let Some(expected) = hashes.get(&asset.name) {
+                checksums::verify(&path, &asset.name, expected)?;
+            }
+            Ok::<_, AppError>(path)
+        }
+        .await;
+        let path = match path {
+            Ok(path) => Arc::new(path),
+            Err(error) => {
+                for device in 0..devices.len() {
+                    send(device, InstallState::Failed(error.to_string()));
+                }
+                return;
+            }
+        };
+
+        let sha256 = report::sha256(&path).ok();
+        let config = Arc::new(config);
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_INSTALLS));
+        let mut installs = Vec::new();
+        for (index, device) in devices.into_iter().enumerate() {
+            send(index, InstallState::Queued);
+            let (config, path, permits) = (config.clone(), path.clone(), permits.clone());
+            let (updates, sha256) = (updates.clone(), sha256.clone());
+            installs.push(tokio::spawn(async move {
+                let send = |state: InstallState| {
+                    let _ = updates.send(InstallUpdate {
+                        device: index,
+                        sha256: state.is_finished().then(|| sha256.clone()).flatten(),
+                        state,
+                    });
+                };
+                let Ok(_permit) = permits.acquire_owned().await else {
+                    return;
+                };
+
+                send(InstallState::Installing);
+                let replace = Replace {
+                    allow_downgrade: config.allow_downgrade,
+                    ..Replace::default()
+                };
+                let result = install::install_apk(
+                    &config,
+                    &device.serial,
+                    &path,
+                    replace,
+                    |_, _, _| {},
+                    |_| {},
+                )
+                .await;
+                send(match result {
+                    Ok(()) => InstallState::Installed,
+                    Err(error) => InstallState::Failed(error.to_string()),
+                });
+            }));
+        }
+        for install in installs {
+            let _ = install.await;
+        }
+        if install::is_temporary(&path) {
+            let _ = tokio::fs::remove_file(path.as_path()).await;
+        }
+    });
+}
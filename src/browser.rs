@@ -0,0 +1,34 @@
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Opens the URL with the default browser, without waiting for it.
+pub fn open(url: &str) -> io::Result<()> {
+    opener(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("open");
+    command.arg(url);
+    command
+}
+
+#[cfg(windows)]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("cmd");
+    // the empty title keeps `start` from taking the URL as the window title
+    command.args(["/C", "start", "", url]);
+    command
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(url);
+    command
+}
@@ -0,0 +1,271 @@
+use std::fs::{self, File, FileTimes};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::github::{Asset, GithubClient, Validators};
+
+/// Limits the cache is pruned to, unset ones are not enforced.
+#[derive(Clone, Default)]
+pub struct PrunePolicy {
+    /// APKs not used for longer are deleted.
+    pub max_age: Option<Duration>,
+    /// The least recently used APKs are deleted until the rest fit.
+    pub max_size: Option<u64>,
+}
+
+impl PrunePolicy {
+    pub fn is_set(&self) -> bool {
+        self.max_age.is_some() || self.max_size.is_some()
+    }
+}
+
+/// Downloaded APKs kept per asset id, e.g. in `~/.cache/github_install/<host>/<owner>/<repo>`.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(config: &Config) -> Self {
+        let dir = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("github_install")
+            .join(&config.host)
+            .join(&config.owner)
+            .join(&config.repo);
+        Self { dir }
+    }
+
+    pub fn apk_path(&self, asset_id: i32) -> PathBuf {
+        self.dir.join(format!("{}.apk", asset_id))
+    }
+
+    fn validators_path(&self, asset_id: i32) -> PathBuf {
+        self.dir.join(format!("{}.json", asset_id))
+    }
+
+    /// Validators of the cached APK, `None` if it is not cached.
+    fn validators(&self, asset_id: i32) -> Option<Validators> {
+        if !self.contains(asset_id) {
+            return None;
+        }
+        fs::read_to_string(self.validators_path(asset_id))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    pub fn contains(&self, asset_id: i32) -> bool {
+        self.apk_path(asset_id).exists()
+    }
+
+    /// Deletes the cached APK of the asset and returns the number of bytes freed.
+    pub fn remove(&self, asset_id: i32) -> io::Result<u64> {
+        let path = self.apk_path(asset_id);
+        let size = fs::metadata(&path)?.len();
+        fs::remove_file(&path)?;
+        // the validators are useless without the file
+        let _ = fs::remove_file(self.validators_path(asset_id));
+        Ok(size)
+    }
+
+    /// Deletes everything cached for the repository and returns the number of bytes freed.
+    pub fn clear(&self) -> io::Result<u64> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(error) => return Err(error),
+        };
+
+        let mut freed = 0;
+        for entry in entries {
+            let entry = entry?;
+            freed += entry.metadata()?.len();
+            fs::remove_file(entry.path())?;
+        }
+        Ok(freed)
+    }
+
+    /// Deletes the APKs exceeding the policy, least recently used first, and returns the number of
+    /// bytes freed.
+    pub fn prune(&self, policy: &PrunePolicy) -> io::Result<u64> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(error) => return Err(error),
+        };
+
+        // (last used, size, asset id)
+        let mut apks = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let asset_id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".apk"))
+                .and_then(|id| id.parse::<i32>().ok());
+            let Some(asset_id) = asset_id else {
+                continue;
+            };
+            let metadata = fs::metadata(&path)?;
+            let used = metadata.accessed().or_else(|_| metadata.modified())?;
+            apks.push((used, metadata.len(), asset_id));
+        }
+        apks.sort_by_key(|&(used, _, _)| used);
+
+        let now = SystemTime::now();
+        let mut total: u64 = apks.iter().map(|&(_, size, _)| size).sum();
+        let mut freed = 0;
+        for (used, size, asset_id) in apks {
+            let expired = policy
+                .max_age
+                .is_some_and(|max_age| now.duration_since(used).unwrap_or_default() > max_age);
+            let too_large = policy.max_size.is_some_and(|max_size| total > max_size);
+            if !expired && !too_large {
+                continue;
+            }
+            freed += self.remove(asset_id)?;
+            total -= size;
+        }
+        Ok(freed)
+    }
+
+    /// Returns the cached APK, downloading it first unless GitHub reports it as unchanged.
+    pub async fn fetch(
+        &self,
+        github: &GithubClient,
+        asset: &Asset,
+        on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<PathBuf, AppError> {
+        let asset_id = asset.id;
+        let path = self.apk_path(asset_id);
+        let cached = self.validators(asset_id);
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // download next to the cached file, so an interrupted download does not replace it
+        let partial = self.dir.join(format!("{}.part", asset_id));
+        let download = github
+            .download_asset_if_modified(
+                asset,
+                &partial.to_string_lossy(),
+                cached.as_ref(),
+                on_progress,
+            )
+            .await?;
+
+        if let Some(validators) = download {
+            tokio::fs::rename(&partial, &path).await?;
+            let json = serde_json::to_string(&validators).map_err(std::io::Error::other)?;
+            tokio::fs::write(self.validators_path(asset_id), json).await?;
+        }
+        // file systems mounted with noatime would never update the access time pruning relies on
+        File::open(&path)?.set_times(FileTimes::new().set_accessed(SystemTime::now()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const ASSET_ID: i32 = 42;
+
+    /// Serves `body` with `etag`, or `304 Not Modified` to requests sending `etag` along.
+    /// Returns the URL and the number of full downloads.
+    async fn serve(etag: &'static str, body: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let downloads = Arc::new(AtomicUsize::new(0));
+        let counter = downloads.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 4096];
+                let length = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..length]).to_lowercase();
+                let response = if request.contains(&format!("if-none-match: {}", etag)) {
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        etag,
+                        body.len(),
+                        body
+                    )
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{}/app.apk", address), downloads)
+    }
+
+    fn cache(name: &str) -> Cache {
+        let dir =
+            std::env::temp_dir().join(format!("github_install-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        Cache { dir }
+    }
+
+    fn github() -> GithubClient {
+        let mut config = Config::defaults();
+        config.direct_download = true;
+        GithubClient::new(&config)
+    }
+
+    fn asset(url: &str) -> Asset {
+        serde_json::from_value(json!({
+            "id": ASSET_ID,
+            "name": "app.apk",
+            "browser_download_url": url,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reuses_the_cached_apk_when_not_modified() {
+        let cache = cache("not-modified");
+        let (url, downloads) = serve("\"v1\"", "first build").await;
+        let asset = asset(&url);
+
+        let path = cache.fetch(&github(), &asset, |_, _| {}).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first build");
+        assert_eq!(cache.validators(ASSET_ID).unwrap().etag.unwrap(), "\"v1\"");
+
+        let again = cache.fetch(&github(), &asset, |_, _| {}).await.unwrap();
+        assert_eq!(again, path);
+        assert_eq!(fs::read_to_string(&again).unwrap(), "first build");
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_the_apk_again_when_modified() {
+        let cache = cache("modified");
+        let (url, _) = serve("\"v1\"", "first build").await;
+        cache
+            .fetch(&github(), &asset(&url), |_, _| {})
+            .await
+            .unwrap();
+
+        // the same asset id, now served with a new ETag
+        let (url, downloads) = serve("\"v2\"", "second build").await;
+        let path = cache
+            .fetch(&github(), &asset(&url), |_, _| {})
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second build");
+        assert_eq!(cache.validators(ASSET_ID).unwrap().etag.unwrap(), "\"v2\"");
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+use crate::github::Release;
+
+/// Tags limiting the releases of the changelog, both included.
+#[derive(Default, Clone)]
+pub struct Range {
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// Concatenates the notes of the releases in the range into a Markdown document, newest first.
+///
+/// `releases` are expected newest first, as GitHub lists them.
+pub fn render(releases: &[Release], range: &Range) -> Result<String, String> {
+    let position = |tag: &str| {
+        releases
+            .iter()
+            .position(|release| release.tag_name == tag)
+            .ok_or_else(|| format!("No release tagged '{}'", tag))
+    };
+    let newest = range
+        .until
+        .as_deref()
+        .map(position)
+        .transpose()?
+        .unwrap_or(0);
+    let oldest = match range.since.as_deref() {
+        Some(tag) => position(tag)?,
+        None => releases.len().saturating_sub(1),
+    };
+    if newest > oldest {
+        return Err("--until has to be newer than --since".to_string());
+    }
+
+    let mut changelog = String::from("# Changelog\n");
+    for release in releases.iter().take(oldest + 1).skip(newest) {
+        changelog += &format!("\n## {}", release.tag_name);
+        if let Some(published_at) = release.published_at {
+            changelog += &format!(" ({})", published_at.format("%Y-%m-%d"));
+        }
+        changelog += "\n\n";
+        changelog += release.body.trim();
+        changelog += "\n";
+    }
+    Ok(changelog)
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::github::{Asset, GithubClient};
+use crate::report;
+
+/// Names of combined checksum files, compared case-insensitively.
+const MANIFEST_NAMES: [&str; 3] = ["sha256sums", "sha256sums.txt", "checksums.txt"];
+
+/// Expected SHA-256 of the assets of a release, taken from its checksum manifest.
+pub enum Checksums {
+    /// Not fetched yet.
+    Pending,
+    Loading,
+    /// Hashes by file name, empty if the release has no manifest.
+    Loaded(HashMap<String, String>),
+    Failed(String),
+}
+
+impl Checksums {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match self {
+            Checksums::Loaded(hashes) => hashes.get(name).map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+/// The `SHA256SUMS` asset of a release, if it has one.
+pub fn manifest(assets: &[Asset]) -> Option<&Asset> {
+    assets.iter().find(|asset| {
+        MANIFEST_NAMES
+            .iter()
+            .any(|name| asset.name.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Downloads and parses the manifest of the release, no hashes if it has none.
+pub async fn load(
+    github: &GithubClient,
+    assets: &[Asset],
+) -> Result<HashMap<String, String>, AppError> {
+    match manifest(assets) {
+        Some(manifest) => Ok(parse(&github.download_text(manifest).await?)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Reads lines of `sha256sum` (`<hash>  <file>`, `*` before binary files) and of BSD `shasum`
+/// (`SHA256 (<file>) = <hash>`), skipping anything else.
+pub fn parse(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (hash, name) = match line.strip_prefix("SHA256 (") {
+                Some(rest) => {
+                    let (name, hash) = rest.rsplit_once(") = ")?;
+                    (hash, name)
+                }
+                None => {
+                    let (hash, name) = line.split_once(char::is_whitespace)?;
+                    (hash, name.trim_start().trim_start_matches('*'))
+                }
+            };
+            let is_sha256 = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+            let name = name.trim_start_matches("./");
+            is_sha256.then(|| (name.to_string(), hash.to_ascii_lowercase()))
+        })
+        .collect()
+}
+
+/// Fails unless the file has the expected SHA-256.
+pub fn verify(path: &Path, name: &str, expected: &str) -> Result<(), AppError> {
+    let actual = report::sha256(path)?;
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(AppError::ChecksumMismatch {
+            name: name.to_string(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
@@ -0,0 +1,11 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::{self, Write};
+
+/// Puts the text into the system clipboard using the OSC 52 escape sequence, which the terminal
+/// forwards to the clipboard, also over SSH.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
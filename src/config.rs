@@ -0,0 +1,818 @@
+//! Settings are resolved from these sources, later ones taking precedence:
+//!
+//! 1. the config file (see [`config_path`])
+//! 2. a `.env` file in the current directory
+//! 3. the real environment, variables already set are never overridden by `.env`
+//! 4. command line arguments
+//!
+//! The GitHub host follows the `gh` CLI: `$GH_HOST`, otherwise `host` from the config file,
+//! otherwise `github.com`. The token is taken from the first variable set of
+//!
+//! 1. `$GH_ACCESS_TOKEN`
+//! 2. `$GH_ENTERPRISE_TOKEN` or `$GITHUB_ENTERPRISE_TOKEN` for hosts other than `github.com`
+//! 3. `$GH_TOKEN` or `$GITHUB_TOKEN` for `github.com`
+//! 4. the token file saved by the first-run setup (see [`token_path`])
+
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fmt, fs, io};
+
+use crate::adb::{Direction, PortMapping};
+use crate::cache::PrunePolicy;
+use crate::changelog;
+use crate::github::{DEFAULT_API_VERSION, MAX_PER_PAGE};
+use crate::hooks::PostInstall;
+use crate::keys::{Action, Keymap, Keys};
+use crate::sort::{Sort, SortKey, SortOrder};
+
+pub const GITHUB_HOST: &str = "github.com";
+const SORT_FILE: &str = "sort.json";
+/// Where the push only action copies APKs to unless configured otherwise.
+const DEFAULT_PUSH_DIR: &str = "/sdcard/Download";
+const DEFAULT_STALE_DAYS: u64 = 90;
+/// KiB, several response chunks are collected before a write to disk.
+const DEFAULT_WRITE_BUFFER: usize = 256;
+
+/// Settings resolved from the environment and the command line.
+#[derive(Clone)]
+pub struct Config {
+    /// Personal access token, public repositories are accessed anonymously without one.
+    pub token: Option<String>,
+    /// `github.com` or the host of a GitHub Enterprise Server.
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+    /// Further repositories whose releases are listed along with the ones of `owner`/`repo`.
+    pub more_repos: Vec<Repo>,
+    pub api: Api,
+    /// Only releases whose tag matches are listed and installable.
+    pub tag_filter: Option<Regex>,
+    /// Releases requested per page, at most 100.
+    pub per_page: usize,
+    /// Only the newest releases up to this number are fetched.
+    pub limit: Option<usize>,
+    /// Patterns like `*-arm64-v8a.apk` selecting the asset to install, in order of priority.
+    pub asset_patterns: Vec<String>,
+    /// Android user the APK gets installed for (`pm install --user <id>`).
+    pub user_id: u32,
+    /// Serial of the device to install to, the first ready device is used if unset.
+    pub serial: Option<String>,
+    /// `host:port` of a remote device the ADB server connects to first.
+    pub connect: Option<String>,
+    /// Set up on the device before installing and removed when done.
+    pub port_mappings: Vec<PortMapping>,
+    /// Warn before pushing APKs larger than this many bytes.
+    pub warn_apk_size: Option<u64>,
+    /// How long `pm install` may take before it is considered stuck.
+    pub install_timeout: Duration,
+    /// Compare the APK signature with the installed app before installing.
+    pub check_signature: bool,
+    /// Install with `-d` when the device has a newer version, without asking.
+    pub allow_downgrade: bool,
+    /// Launch the app after installing and save a screenshot of the device into this directory.
+    pub screenshot_dir: Option<PathBuf>,
+    /// How long to wait after launching the app before taking the screenshot.
+    pub screenshot_delay: Duration,
+    /// Install the release with this tag, tag prefix (`release-2.*`) or commit (`@abc123f`)
+    /// without starting the TUI.
+    pub install_tag: Option<String>,
+    /// Name or index among the installable assets of the asset to install with `--install`.
+    pub asset: Option<String>,
+    /// Print the notes of these releases instead of browsing them.
+    pub changelog: Option<changelog::Range>,
+    /// APK on disk to install instead of browsing the releases.
+    pub local_apk: Option<PathBuf>,
+    /// Pick the APK for the ABI of the device, falling back to other variants if it fails.
+    pub auto_abi: bool,
+    /// Keep downloaded APKs and only download them again if they changed.
+    pub cache: bool,
+    /// Limits for pruning the cache.
+    pub cache_limits: PrunePolicy,
+    /// Prune the cache to its limits when starting the TUI.
+    pub auto_prune: bool,
+    /// Prune the cache to its limits instead of browsing the releases.
+    pub prune_cache: bool,
+    /// List the releases first and fetch notes and assets of a release once it gets selected.
+    pub lazy: bool,
+    /// Download assets from their `browser_download_url` instead of the API endpoint.
+    pub direct_download: bool,
+    /// Bytes of a download buffered before they are written to disk.
+    pub write_buffer: usize,
+    /// Sent as `X-GitHub-Api-Version` with every REST request.
+    pub api_version: String,
+    /// Trusted along with the system's root certificates, e.g. the CA of a TLS intercepting proxy.
+    pub ca_certificates: Vec<reqwest::Certificate>,
+    /// Accept any certificate, for testing only.
+    pub insecure: bool,
+    /// Poll for new releases while the TUI is open.
+    pub watch: bool,
+    /// How often `watch` polls.
+    pub poll_interval: Duration,
+    /// Install new releases found by `watch` to the device.
+    pub auto_install: bool,
+    /// Leave the APK pushed for `pm install` on the device instead of removing it afterwards.
+    pub keep_device_apk: bool,
+    /// Directory on the device APKs are copied to by the push only action.
+    pub push_dir: String,
+    /// Shell commands run after each successful install, in order.
+    pub post_install: Vec<PostInstall>,
+    /// Directory installed APKs are archived in, they are deleted if unset.
+    pub keep_apk_dir: Option<PathBuf>,
+    /// Where the installs of the session are reported to on exit.
+    pub report: Option<PathBuf>,
+    /// Where downloaded assets are saved, `<downloads>/{repo}/{tag}/{asset}` if unset.
+    pub output_template: Option<OutputTemplate>,
+    pub view: ViewMode,
+    pub scroll_mode: ScrollMode,
+    /// Start without the bar listing the keys at the bottom.
+    pub hide_actions: bool,
+    /// Ask before installing a release published longer ago than this if newer ones exist.
+    pub stale_after: Option<Duration>,
+    /// The sort chosen in the TUI last time, otherwise the configured one.
+    pub sort: Sort,
+    pub keymap: Keymap,
+}
+
+/// A repository given as `owner/repo`.
+#[derive(Clone, PartialEq)]
+pub struct Repo {
+    pub owner: String,
+    pub repo: String,
+}
+
+impl fmt::Display for Repo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.repo)
+    }
+}
+
+impl FromStr for Repo {
+    type Err = String;
+
+    fn from_str(slug: &str) -> Result<Self, String> {
+        match slug.split_once('/') {
+            Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() => Ok(Self {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            }),
+            _ => Err(format!(
+                "Invalid repository '{}', expected owner/repo",
+                slug
+            )),
+        }
+    }
+}
+
+/// Path of a downloaded asset with placeholders, e.g. `~/apks/{owner}/{repo}/{tag}/{asset}`.
+#[derive(Clone)]
+pub struct OutputTemplate(String);
+
+const PLACEHOLDERS: [&str; 4] = ["owner", "repo", "tag", "asset"];
+
+impl FromStr for OutputTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, String> {
+        let invalid =
+            |reason: String| format!("Invalid output template '{}': {}", template, reason);
+        let mut rest = template;
+        let mut has_asset = false;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(invalid("'}' without '{'".to_string()));
+            }
+            let end = start
+                + rest[start..]
+                    .find('}')
+                    .ok_or_else(|| invalid("'{' without '}'".to_string()))?;
+            let name = &rest[start + 1..end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(invalid(format!(
+                    "unknown placeholder {{{}}}, known are {{owner}}, {{repo}}, {{tag}} and \
+                     {{asset}}",
+                    name
+                )));
+            }
+            has_asset |= name == "asset";
+            rest = &rest[end + 1..];
+        }
+        if !has_asset {
+            return Err(invalid("{asset} is missing".to_string()));
+        }
+        Ok(Self(template.to_string()))
+    }
+}
+
+impl OutputTemplate {
+    /// Fills in the placeholders, each value made safe to use as a single file name.
+    pub fn expand(&self, owner: &str, repo: &str, tag: &str, asset: &str) -> PathBuf {
+        // safe values contain no braces, so later placeholders cannot be smuggled in
+        let path = self
+            .0
+            .replace("{owner}", &path_safe(owner))
+            .replace("{repo}", &path_safe(repo))
+            .replace("{tag}", &path_safe(tag))
+            .replace("{asset}", &path_safe(asset));
+        match (path.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(path),
+        }
+    }
+}
+
+/// Replaces separators, characters Windows forbids in file names and braces with `_`, as well as
+/// names like `..` that would leave the directory.
+pub fn path_safe(value: &str) -> String {
+    let safe: String = value
+        .chars()
+        .map(|c| {
+            if c.is_control() || r#"/\:*?"<>|{}"#.contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if safe.chars().all(|c| c == '.') {
+        "_".repeat(safe.len().max(1))
+    } else {
+        safe
+    }
+}
+
+/// GitHub API used to fetch the releases.
+#[derive(Deserialize, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Api {
+    /// Paginated REST requests, works without a token for public repositories.
+    #[default]
+    Rest,
+    /// A single query per 100 releases including their assets, requires a token.
+    Graphql,
+}
+
+/// How the release list scrolls when the selection moves.
+#[derive(Deserialize, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollMode {
+    /// Scroll only once the selection reaches the edge of the list.
+    #[default]
+    Edge,
+    /// Keep the selection in the middle of the list.
+    Center,
+}
+
+/// How much detail each row of the release list shows.
+#[derive(Deserialize, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewMode {
+    /// Tag name only.
+    #[default]
+    Compact,
+    /// Tag name, date, size and status.
+    Detailed,
+}
+
+impl ViewMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            ViewMode::Compact => ViewMode::Detailed,
+            ViewMode::Detailed => ViewMode::Compact,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ViewMode::Compact => "compact",
+            ViewMode::Detailed => "detailed",
+        }
+    }
+}
+
+/// Contents of the optional config file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FileConfig {
+    host: Option<String>,
+    /// `owner/repo` listed along with `$GH_OWNER`/`$GH_REPO`.
+    repos: Vec<String>,
+    api: Api,
+    tag_filter: Option<String>,
+    per_page: Option<usize>,
+    limit: Option<usize>,
+    asset_patterns: Vec<String>,
+    connect: Option<String>,
+    port_mappings: Vec<PortMapping>,
+    /// Seconds, defaults to 120.
+    install_timeout: Option<u64>,
+    check_signature: bool,
+    screenshot_dir: Option<PathBuf>,
+    /// Seconds, defaults to 3.
+    screenshot_delay: Option<u64>,
+    keep_apk_dir: Option<PathBuf>,
+    push_dir: Option<String>,
+    keep_device_apk: bool,
+    post_install: Vec<String>,
+    report: Option<PathBuf>,
+    output_template: Option<String>,
+    cache: bool,
+    /// Days, APKs not used for longer are pruned.
+    cache_max_age: Option<u64>,
+    /// MiB, the least recently used APKs are pruned beyond.
+    cache_max_size: Option<u64>,
+    /// MiB, larger APKs get a warning before they are pushed.
+    warn_apk_size: Option<u64>,
+    auto_prune: bool,
+    lazy: bool,
+    direct_download: bool,
+    /// KiB, defaults to 256.
+    write_buffer: Option<usize>,
+    api_version: Option<String>,
+    /// PEM bundle or DER file of extra root certificates.
+    ca_cert: Option<PathBuf>,
+    watch: bool,
+    /// Seconds, defaults to 60.
+    poll_interval: Option<u64>,
+    auto_install: bool,
+    auto_abi: bool,
+    view: ViewMode,
+    scroll_mode: ScrollMode,
+    hide_actions: bool,
+    /// Days, defaults to 90, 0 installs old releases without asking.
+    stale_after: Option<u64>,
+    sort: SortKey,
+    sort_order: SortOrder,
+    keybindings: HashMap<Action, Keys>,
+}
+
+impl Config {
+    /// Reads the environment, the config file and the command line arguments.
+    pub fn load() -> Result<Self, String> {
+        if let Err(error) = dotenvy::dotenv() {
+            if !error.not_found() {
+                return Err(format!("Invalid .env file: {}", error));
+            }
+        }
+
+        let mut config = Self::from_file(read_file()?)?;
+        if let Ok(host) = env::var("GH_HOST") {
+            config.host = host;
+        }
+        config.token = token(&config.host);
+        config.serial = env::var("ANDROID_SERIAL").ok();
+        if let Some(sort) =
+            Sort::load_saved().map_err(|error| format!("Invalid {}: {}", SORT_FILE, error))?
+        {
+            config.sort = sort;
+        }
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--user" => config.user_id = parse_value(&arg, args.next())?,
+                "--serial" => {
+                    config.serial = Some(args.next().ok_or("--serial requires a device serial")?);
+                }
+                "--connect" => {
+                    config.connect = Some(args.next().ok_or("--connect requires host:port")?);
+                }
+                "--forward" => {
+                    let (local, remote) = (args.next(), args.next());
+                    let (Some(local), Some(remote)) = (local, remote) else {
+                        return Err("--forward requires a local and a remote socket".to_string());
+                    };
+                    config.port_mappings.push(PortMapping {
+                        direction: Direction::Forward,
+                        local,
+                        remote,
+                    });
+                }
+                "--reverse" => {
+                    let (remote, local) = (args.next(), args.next());
+                    let (Some(remote), Some(local)) = (remote, local) else {
+                        return Err("--reverse requires a remote and a local socket".to_string());
+                    };
+                    config.port_mappings.push(PortMapping {
+                        direction: Direction::Reverse,
+                        local,
+                        remote,
+                    });
+                }
+                "--install-timeout" => {
+                    config.install_timeout = Duration::from_secs(parse_value(&arg, args.next())?);
+                }
+                "--check-signature" => config.check_signature = true,
+                "--allow-downgrade" => config.allow_downgrade = true,
+                "--cache" => config.cache = true,
+                "--lazy" => config.lazy = true,
+                "--direct-download" => config.direct_download = true,
+                "--write-buffer" => {
+                    config.write_buffer = kibibytes(parse_value(&arg, args.next())?);
+                }
+                "--ca-cert" => {
+                    let path = args.next().ok_or("--ca-cert requires a path")?;
+                    config.ca_certificates = read_certificates(Path::new(&path))?;
+                }
+                "--insecure" => config.insecure = true,
+                "--watch" => config.watch = true,
+                "--poll-interval" => {
+                    config.poll_interval = Duration::from_secs(parse_value(&arg, args.next())?);
+                }
+                "--auto-install" => config.auto_install = true,
+                "--repo" => {
+                    let slug = args.next().ok_or("--repo requires owner/repo")?;
+                    config.more_repos.push(slug.parse()?);
+                }
+                "--prune-cache" => config.prune_cache = true,
+                "--cache-max-age" => {
+                    config.cache_limits.max_age = Some(days(parse_value(&arg, args.next())?));
+                }
+                "--cache-max-size" => {
+                    config.cache_limits.max_size = Some(mebibytes(parse_value(&arg, args.next())?));
+                }
+                "--warn-apk-size" => {
+                    config.warn_apk_size = Some(mebibytes(parse_value(&arg, args.next())?));
+                }
+                "--auto-abi" => config.auto_abi = true,
+                "--screenshot-dir" => {
+                    let dir = args.next().ok_or("--screenshot-dir requires a directory")?;
+                    config.screenshot_dir = Some(PathBuf::from(dir));
+                }
+                "--install" => {
+                    config.install_tag = Some(
+                        args.next()
+                            .ok_or("--install requires a tag, a tag prefix* or @commit")?,
+                    );
+                }
+                "--asset" => {
+                    config.asset = Some(args.next().ok_or("--asset requires a name or index")?);
+                }
+                "--since" => {
+                    let tag = args.next().ok_or("--since requires a tag")?;
+                    config.changelog.get_or_insert_with(Default::default).since = Some(tag);
+                }
+                "--until" => {
+                    let tag = args.next().ok_or("--until requires a tag")?;
+                    config.changelog.get_or_insert_with(Default::default).until = Some(tag);
+                }
+                "--local-apk" => {
+                    let path = args.next().ok_or("--local-apk requires a path")?;
+                    config.local_apk = Some(PathBuf::from(path));
+                }
+                "--keep-apk" => {
+                    let dir = args.next().ok_or("--keep-apk requires a directory")?;
+                    config.keep_apk_dir = Some(PathBuf::from(dir));
+                }
+                "--keep-device-apk" => config.keep_device_apk = true,
+                "--push-dir" => {
+                    config.push_dir = args.next().ok_or("--push-dir requires a directory")?;
+                }
+                "--post-install" => {
+                    let command = args.next().ok_or("--post-install requires a command")?;
+                    config.post_install.push(command.parse()?);
+                }
+                "--report" => {
+                    let path = args.next().ok_or("--report requires a path")?;
+                    config.report = Some(PathBuf::from(path));
+                }
+                "--output-template" => {
+                    let template = args.next().ok_or("--output-template requires a template")?;
+                    config.output_template = Some(template.parse()?);
+                }
+                "--hide-actions" => config.hide_actions = true,
+                "--stale-after" => {
+                    config.stale_after = stale_after(parse_value(&arg, args.next())?);
+                }
+                "--sort" => config.sort.key = parse_value(&arg, args.next())?,
+                "--sort-order" => config.sort.order = parse_value(&arg, args.next())?,
+                "--per-page" => config.per_page = parse_value(&arg, args.next())?,
+                "--limit" => config.limit = Some(parse_value(&arg, args.next())?),
+                "--api-version" => {
+                    config.api_version = args.next().ok_or("--api-version requires a version")?;
+                }
+                "--tag-filter" => {
+                    let pattern = args.next().ok_or("--tag-filter requires a regex")?;
+                    config.tag_filter = Some(parse_regex(&pattern)?);
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+
+        if !(1..=MAX_PER_PAGE).contains(&config.per_page) {
+            return Err(format!("per_page must be between 1 and {}", MAX_PER_PAGE));
+        }
+        if config.write_buffer == 0 {
+            return Err("write_buffer must be at least 1 KiB".to_string());
+        }
+        if config.limit == Some(0) {
+            return Err("limit must be at least 1".to_string());
+        }
+        if config.poll_interval.is_zero() {
+            return Err("poll_interval must be at least 1 second".to_string());
+        }
+        if config.auto_install && !config.watch {
+            return Err("auto_install requires watch".to_string());
+        }
+
+        // a local APK is installed without asking GitHub
+        if config.local_apk.is_none() {
+            config.owner = require_env("GH_OWNER")?;
+            config.repo = require_env("GH_REPO")?;
+        }
+
+        Ok(config)
+    }
+
+    /// The configuration with the settings of the config file and defaults for the rest.
+    fn from_file(file: FileConfig) -> Result<Self, String> {
+        Ok(Self {
+            token: None,
+            host: file.host.unwrap_or_else(|| GITHUB_HOST.to_string()),
+            owner: String::new(),
+            repo: String::new(),
+            more_repos: file
+                .repos
+                .iter()
+                .map(|slug| slug.parse())
+                .collect::<Result<_, _>>()?,
+            api: file.api,
+            tag_filter: file.tag_filter.as_deref().map(parse_regex).transpose()?,
+            per_page: file.per_page.unwrap_or(MAX_PER_PAGE),
+            limit: file.limit,
+            asset_patterns: file.asset_patterns,
+            user_id: 0,
+            serial: None,
+            connect: file.connect,
+            port_mappings: file.port_mappings,
+            install_timeout: Duration::from_secs(file.install_timeout.unwrap_or(120)),
+            check_signature: file.check_signature,
+            allow_downgrade: false,
+            screenshot_dir: file.screenshot_dir,
+            screenshot_delay: Duration::from_secs(file.screenshot_delay.unwrap_or(3)),
+            keep_apk_dir: file.keep_apk_dir,
+            keep_device_apk: file.keep_device_apk,
+            push_dir: file
+                .push_dir
+                .unwrap_or_else(|| DEFAULT_PUSH_DIR.to_string()),
+            post_install: file
+                .post_install
+                .iter()
+                .map(|command| command.parse())
+                .collect::<Result<_, _>>()?,
+            report: file.report,
+            output_template: file
+                .output_template
+                .as_deref()
+                .map(str::parse)
+                .transpose()?,
+            cache: file.cache,
+            cache_limits: PrunePolicy {
+                max_age: file.cache_max_age.map(days),
+                max_size: file.cache_max_size.map(mebibytes),
+            },
+            warn_apk_size: file.warn_apk_size.map(mebibytes),
+            auto_prune: file.auto_prune,
+            prune_cache: false,
+            lazy: file.lazy,
+            direct_download: file.direct_download,
+            write_buffer: kibibytes(file.write_buffer.unwrap_or(DEFAULT_WRITE_BUFFER)),
+            api_version: file
+                .api_version
+                .unwrap_or_else(|| DEFAULT_API_VERSION.to_string()),
+            ca_certificates: match &file.ca_cert {
+                Some(path) => read_certificates(path)?,
+                None => Vec::new(),
+            },
+            insecure: false,
+            watch: file.watch,
+            poll_interval: Duration::from_secs(file.poll_interval.unwrap_or(60)),
+            auto_install: file.auto_install,
+            auto_abi: file.auto_abi,
+            local_apk: None,
+            changelog: None,
+            install_tag: None,
+            asset: None,
+            view: file.view,
+            scroll_mode: file.scroll_mode,
+            hide_actions: file.hide_actions,
+            stale_after: stale_after(file.stale_after.unwrap_or(DEFAULT_STALE_DAYS)),
+            sort: Sort {
+                key: file.sort,
+                order: file.sort_order,
+            },
+            keymap: Keymap::new(file.keybindings)?,
+        })
+    }
+
+    /// The defaults, without reading the environment, any file or the arguments.
+    #[cfg(test)]
+    pub fn defaults() -> Self {
+        Self::from_file(FileConfig::default()).unwrap()
+    }
+
+    /// All listed repositories, `owner`/`repo` first.
+    pub fn repos(&self) -> Vec<Repo> {
+        let main = Repo {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+        };
+        let mut repos = vec![main];
+        for repo in &self.more_repos {
+            if !repos.contains(repo) {
+                repos.push(repo.clone());
+            }
+        }
+        repos
+    }
+
+    /// The same settings for another repository.
+    pub fn for_repo(&self, repo: &Repo) -> Self {
+        Self {
+            owner: repo.owner.clone(),
+            repo: repo.repo.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(arg: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", arg))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, arg))
+}
+
+/// Reads the certificates of a PEM bundle, or a single DER encoded one.
+fn read_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, String> {
+    let invalid =
+        |error: reqwest::Error| format!("Invalid CA certificate {}: {}", path.display(), error);
+    let content =
+        fs::read(path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+    let certificates = if String::from_utf8_lossy(&content).contains("-----BEGIN") {
+        reqwest::Certificate::from_pem_bundle(&content).map_err(invalid)?
+    } else {
+        vec![reqwest::Certificate::from_der(&content).map_err(invalid)?]
+    };
+    if certificates.is_empty() {
+        return Err(format!("No certificates in {}", path.display()));
+    }
+    Ok(certificates)
+}
+
+fn stale_after(days_count: u64) -> Option<Duration> {
+    (days_count > 0).then(|| days(days_count))
+}
+
+fn days(days: u64) -> Duration {
+    Duration::from_secs(days * 24 * 60 * 60)
+}
+
+fn kibibytes(kibibytes: usize) -> usize {
+    kibibytes * 1024
+}
+
+fn mebibytes(mebibytes: u64) -> u64 {
+    mebibytes * 1024 * 1024
+}
+
+/// Looks the token up the way `gh` does for the host, preferring our own variable.
+fn token(host: &str) -> Option<String> {
+    let keys: &[&str] = if host == GITHUB_HOST {
+        &["GH_ACCESS_TOKEN", "GH_TOKEN", "GITHUB_TOKEN"]
+    } else {
+        &[
+            "GH_ACCESS_TOKEN",
+            "GH_ENTERPRISE_TOKEN",
+            "GITHUB_ENTERPRISE_TOKEN",
+        ]
+    };
+    keys.iter().find_map(|key| env::var(key).ok()).or_else(|| {
+        let token = fs::read_to_string(token_path()?).ok()?;
+        Some(token.trim().to_string()).filter(|token| !token.is_empty())
+    })
+}
+
+/// Location of the token saved by the first-run setup, next to the config file.
+pub fn token_path() -> Option<PathBuf> {
+    config_path().map(|path| path.with_file_name("token"))
+}
+
+/// Location of the sort chosen in the TUI, next to the config file.
+pub fn sort_path() -> Option<PathBuf> {
+    config_path().map(|path| path.with_file_name(SORT_FILE))
+}
+
+/// Location of the pinned releases, next to the config file.
+pub fn pins_path() -> Option<PathBuf> {
+    config_path().map(|path| path.with_file_name("pins.json"))
+}
+
+/// Saves the token to [`token_path`], readable only by the current user.
+pub fn save_token(token: &str) -> io::Result<PathBuf> {
+    let path = token_path().ok_or_else(|| io::Error::other("No config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(token.as_bytes())?;
+    Ok(path)
+}
+
+fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|error| format!("Invalid tag filter '{}': {}", pattern, error))
+}
+
+fn require_env(key: &str) -> Result<String, String> {
+    env::var(key).map_err(|_| format!("${} is not set", key))
+}
+
+/// Location of the config file, e.g. `~/.config/github_install/config.toml` on Linux.
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("github_install").join("config.toml"))
+}
+
+fn read_file() -> Result<FileConfig, String> {
+    let Some(path) = config_path() else {
+        return Ok(FileConfig::default());
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|error| format!("Invalid config file {}: {}", path.display(), error)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(FileConfig::default()),
+        Err(error) => Err(format!("Could not read {}: {}", path.display(), error)),
+    }
+}
+
+/// Stores a single setting in the config file, keeping the other settings.
+pub fn save_setting(key: &str, value: toml::Value) -> Result<(), String> {
+    let path = config_path().ok_or("No config directory available")?;
+    let mut table = match fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<toml::Table>()
+            .map_err(|error| format!("Invalid config file {}: {}", path.display(), error))?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+        Err(error) => return Err(format!("Could not read {}: {}", path.display(), error)),
+    };
+    table.insert(key.to_string(), value);
+
+    let content = toml::to_string(&table).map_err(|error| error.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+    }
+    fs::write(&path, content)
+        .map_err(|error| format!("Could not write {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Component, Path};
+
+    fn stays_inside(path: &Path, dir: &str) -> bool {
+        path.starts_with(dir)
+            && path
+                .components()
+                .all(|component| component != Component::ParentDir)
+    }
+
+    #[test]
+    fn path_safe_keeps_values_to_a_single_name() {
+        assert_eq!(path_safe("v1.2.0"), "v1.2.0");
+        assert_eq!(path_safe("release/v1"), "release_v1");
+        assert_eq!(path_safe("..\\..\\evil"), ".._.._evil");
+        assert_eq!(path_safe(".."), "__");
+        assert_eq!(path_safe("."), "_");
+        assert_eq!(path_safe(""), "_");
+        assert_eq!(path_safe("{asset}"), "_asset_");
+    }
+
+    #[test]
+    fn templates_keep_downloads_inside_their_directory() {
+        let template: OutputTemplate = "/downloads/{repo}/{tag}/{asset}".parse().unwrap();
+
+        let path = template.expand("owner", "app", "../../../etc", "../passwd");
+        assert!(stays_inside(&path, "/downloads/app"), "{}", path.display());
+        assert_eq!(path, Path::new("/downloads/app/.._.._.._etc/.._passwd"));
+
+        let path = template.expand("owner", "..", "..", "app.apk");
+        assert!(stays_inside(&path, "/downloads"), "{}", path.display());
+    }
+
+    #[test]
+    fn templates_require_known_placeholders_and_the_asset() {
+        assert!("/downloads/{tag}".parse::<OutputTemplate>().is_err());
+        assert!("/downloads/{version}/{asset}"
+            .parse::<OutputTemplate>()
+            .is_err());
+        assert!("/downloads/{tag/{asset}".parse::<OutputTemplate>().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Request to GitHub failed! {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("GitHub API error: {0}")]
+    Github(String),
+    #[error("{0}")]
+    NoAsset(String),
+    #[error("The file is not an APK, {0}")]
+    NotAnApk(String),
+    #[error("SHA-256 of {name} is {actual}, SHA256SUMS lists {expected}")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Signature of {0} ({1} signed) differs from the installed app, it has to be uninstalled first")]
+    SignatureMismatch(String, String),
+    #[error(
+        "Device has versionCode {installed} of {package}, installing {apk} — downgrade requires -d"
+    )]
+    Downgrade {
+        package: String,
+        installed: u64,
+        apk: u64,
+    },
+    #[error("The APK needs SDK {min} or newer, the device has SDK {device} — installing would fail with INSTALL_FAILED_OLDER_SDK")]
+    OlderSdk { min: u32, device: u32 },
+    #[error("ADB server not running — run `adb start-server`")]
+    AdbServerNotRunning,
+    #[error("No authorized device connected")]
+    NoDevice,
+    #[error(
+        "Device {0} is unauthorized — accept the USB debugging prompt on the device and retry"
+    )]
+    DeviceUnauthorized(String),
+    #[error("Device {0} is not connected")]
+    DeviceNotFound(String),
+    #[error("Could not connect to {0}! {1}")]
+    Connect(String, String),
+    #[error("Could not set up {0}! {1}")]
+    PortMapping(String, String),
+    #[error("Could not talk to the ADB server! {0}")]
+    Adb(String),
+    #[error("Could not install apk on device! {0}")]
+    Install(String),
+    #[error("Install timed out after {0}s")]
+    InstallTimedOut(u64),
+    #[error("User {0} is not allowed to install apps (INSTALL_FAILED_USER_RESTRICTED)")]
+    UserRestricted(u32),
+    #[error("The APK does not support the ABIs of the device ({0})")]
+    IncompatibleAbi(String),
+    #[error("Post-install command failed: {0}")]
+    PostInstall(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl AppError {
+    /// Whether GitHub rejected the token, e.g. because it expired.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, AppError::Http(error) if error.status() == Some(reqwest::StatusCode::UNAUTHORIZED))
+    }
+
+    /// Exit code of the non-interactive modes: 2 no matching release or asset, 3 GitHub request
+    /// or download failed, 4 device or ADB failure, 5 install failed, 1 anything else.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NoAsset(_) => 2,
+            AppError::Http(_)
+            | AppError::Github(_)
+            | AppError::NotAnApk(_)
+            | AppError::ChecksumMismatch { .. } => 3,
+            AppError::AdbServerNotRunning
+            | AppError::NoDevice
+            | AppError::DeviceUnauthorized(_)
+            | AppError::DeviceNotFound(_)
+            | AppError::Connect(..)
+            | AppError::PortMapping(..)
+            | AppError::Adb(_) => 4,
+            AppError::SignatureMismatch(..)
+            | AppError::Downgrade { .. }
+            | AppError::OlderSdk { .. }
+            | AppError::Install(_)
+            | AppError::InstallTimedOut(_)
+            | AppError::UserRestricted(_)
+            | AppError::IncompatibleAbi(_) => 5,
+            AppError::PostInstall(_) | AppError::Io(_) => 1,
+        }
+    }
+}
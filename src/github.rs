@@ -1,71 +1,1200 @@
-use reqwest::Error;
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::header::{
+    HeaderMap, ACCEPT, AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK,
+    RETRY_AFTER, USER_AGENT as USER_AGENT_HEADER,
+};
+use reqwest::{Error, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc::UnboundedSender;
 
+use crate::config::{Api, Config, GITHUB_HOST};
+use crate::error::AppError;
+
+/// Largest page size GitHub allows.
+pub const MAX_PER_PAGE: usize = 100;
+/// REST API version requested unless configured otherwise.
+pub const DEFAULT_API_VERSION: &str = "2022-11-28";
+/// Identifies this tool in GitHub's logs.
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+/// Media type of the JSON responses of the REST API.
+const JSON: &str = "application/vnd.github+json";
+/// How often a request is repeated after GitHub answered it with `Retry-After`.
+const MAX_RATE_LIMIT_RETRIES: usize = 3;
+
+/// Fetches releases with their assets in pages of `$first` releases.
+const RELEASES_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $first: Int!, $cursor: String) {
+  repository(owner: $owner, name: $repo) {
+    releases(first: $first, after: $cursor, orderBy: {field: CREATED_AT, direction: DESC}) {
+      totalCount
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        databaseId
+        tagName
+        name
+        description
+        publishedAt
+        tagCommit { oid }
+        reactionGroups { content reactors { totalCount } }
+        releaseAssets(first: 100) {
+          totalCount
+          nodes { databaseId name contentType size downloadCount createdAt updatedAt downloadUrl }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Fetches only what the release list shows, in pages of `$first` releases.
+const RELEASE_LIST_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $first: Int!, $cursor: String) {
+  repository(owner: $owner, name: $repo) {
+    releases(first: $first, after: $cursor, orderBy: {field: CREATED_AT, direction: DESC}) {
+      totalCount
+      pageInfo { hasNextPage endCursor }
+      nodes { databaseId tagName name publishedAt tagCommit { oid } }
+    }
+  }
+}
+"#;
+
+/// Only the tag and the ids and names of assets are required, so changes to the API or releases
+/// without notes (`"body": null`) do not fail the whole fetch.
 #[derive(Deserialize, Debug)]
 pub struct Release {
+    /// Stays the same when the tag is deleted and created again, unlike `tag_name`.
+    pub id: i64,
     pub tag_name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub body: String,
+    #[serde(default)]
     pub name: Option<String>,
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    /// Branch or commit the tag was created from, the commit the tag points to with GraphQL.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub target_commitish: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub assets: Vec<Asset>,
+    /// Missing from the REST API while nobody reacted.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub reactions: Reactions,
+    /// The release as GitHub returned it.
+    #[serde(skip)]
+    pub raw: Value,
+    /// Only some of the assets were fetched, [`GithubClient::fetch_release`] returns all of them.
+    #[serde(skip)]
+    pub partial: bool,
 }
 
-#[derive(Deserialize, Debug)]
+impl Release {
+    /// Returns the asset to install: the first `.apk`, or else the first asset without an
+    /// extension, whose content gets checked after downloading.
+    pub fn apk_asset(&self) -> Option<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name.ends_with(".apk"))
+            .or_else(|| self.assets.iter().find(|a| !a.name.contains('.')))
+    }
+}
+
+/// Returns the asset matching the first pattern any asset matches, e.g. prefer
+/// `*-arm64-v8a.apk` over `*-universal.apk`. Without patterns [`Release::apk_asset`] is used.
+pub fn resolve_asset<'a>(release: &'a Release, patterns: &[String]) -> Option<&'a Asset> {
+    if patterns.is_empty() {
+        return release.apk_asset();
+    }
+    patterns.iter().find_map(|pattern| {
+        release
+            .assets
+            .iter()
+            .find(|asset| glob_match(pattern, &asset.name))
+    })
+}
+
+/// Returns the installable assets: those matching any of the patterns in order of priority, or
+/// all `.apk` assets without patterns.
+pub fn matching_assets<'a>(release: &'a Release, patterns: &[String]) -> Vec<&'a Asset> {
+    if patterns.is_empty() {
+        return release
+            .assets
+            .iter()
+            .filter(|asset| asset.name.ends_with(".apk"))
+            .collect();
+    }
+
+    let mut assets: Vec<&Asset> = Vec::new();
+    for pattern in patterns {
+        for asset in &release.assets {
+            if glob_match(pattern, &asset.name) && !assets.iter().any(|a| a.id == asset.id) {
+                assets.push(asset);
+            }
+        }
+    }
+    assets
+}
+
+/// Matches `name` against a pattern where `*` matches any characters and `?` a single one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it currently matches up to
+    let mut star = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// How often each reaction was given to a release.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Reactions {
+    #[serde(rename = "+1")]
+    pub thumbs_up: u32,
+    #[serde(rename = "-1")]
+    pub thumbs_down: u32,
+    pub laugh: u32,
+    pub hooray: u32,
+    pub confused: u32,
+    pub heart: u32,
+    pub rocket: u32,
+    pub eyes: u32,
+}
+
+impl Reactions {
+    /// The given reactions as emoji and count, in the order GitHub shows them.
+    pub fn counts(&self) -> Vec<(&'static str, u32)> {
+        [
+            ("👍", self.thumbs_up),
+            ("👎", self.thumbs_down),
+            ("😄", self.laugh),
+            ("🎉", self.hooray),
+            ("😕", self.confused),
+            ("❤️", self.heart),
+            ("🚀", self.rocket),
+            ("👀", self.eyes),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct Asset {
     pub name: String,
+    /// Description set by the maintainer, e.g. `ARM64 build`.
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub browser_download_url: String,
     pub id: i32,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub content_type: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub size: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub download_count: u64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub updated_at: DateTime<Utc>,
 }
 
-pub async fn fetch_releases(owner: &str, repo: &str, token: &str) -> Result<Vec<Release>, Error> {
-    let url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
-    let client = reqwest::Client::new();
+impl Asset {
+    /// The label if the maintainer set one, otherwise the file name.
+    pub fn display_name(&self) -> &str {
+        self.label
+            .as_deref()
+            .filter(|label| !label.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Treats `null` like a missing field.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Access to the releases of a single repository.
+#[derive(Clone)]
+pub struct GithubClient {
+    client: reqwest::Client,
+    /// REST endpoint, `https://api.github.com` or `https://<host>/api/v3` for GitHub Enterprise.
+    api_url: String,
+    graphql_url: String,
+    owner: String,
+    repo: String,
+    token: Option<String>,
+    api: Api,
+    tag_filter: Option<Regex>,
+    per_page: usize,
+    /// Pagination stops once this many releases matching the tag filter are fetched.
+    limit: Option<usize>,
+    direct_download: bool,
+    /// Bytes buffered before downloaded chunks are written to disk.
+    write_buffer: usize,
+    /// Sent as `X-GitHub-Api-Version`.
+    api_version: String,
+    /// Receives messages about rate limits, they are printed to stderr without.
+    notices: Option<UnboundedSender<String>>,
+}
+
+impl GithubClient {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: http_client(config),
+            api_url: api_url(&config.host),
+            graphql_url: graphql_url(&config.host),
+            owner: config.owner.clone(),
+            repo: config.repo.clone(),
+            token: config.token.clone(),
+            api: config.api,
+            tag_filter: config.tag_filter.clone(),
+            per_page: config.per_page,
+            limit: config.limit,
+            direct_download: config.direct_download,
+            write_buffer: config.write_buffer,
+            api_version: config.api_version.clone(),
+            notices: None,
+        }
+    }
+
+    /// Uses another token for the following requests.
+    pub fn set_token(&mut self, token: String) {
+        self.token = Some(token);
+    }
 
-    let auth_header = format!("Bearer {}", token);
-    let response = client
-        .get(&url)
-        .header("User-Agent", "request")
-        .header("Authorization", auth_header)
-        .send()
-        .await?
-        .json::<Vec<Release>>()
-        .await?;
+    /// Sends messages about rate limits and download fallbacks to `notices` instead of stderr.
+    pub fn set_notices(&mut self, notices: UnboundedSender<String>) {
+        self.notices = Some(notices);
+    }
 
-    Ok(response)
+    /// Fetches the releases matching the tag filter, up to the limit, using the configured API.
+    ///
+    /// `on_page` is called before each page is requested with its number and, once known, the
+    /// number of pages.
+    pub async fn fetch_releases(
+        &self,
+        on_page: impl FnMut(usize, Option<usize>),
+    ) -> Result<Vec<Release>, AppError> {
+        let (releases, _) = self.fetch_from(self.first_page(false), on_page).await?;
+        Ok(releases)
+    }
+
+    /// Where fetching the releases starts. `lazy` fetches them without the notes and assets,
+    /// which are fetched with [`Self::fetch_release`] when needed.
+    ///
+    /// The REST API always returns complete releases, so `lazy` only saves time with GraphQL.
+    pub fn first_page(&self, lazy: bool) -> Continuation {
+        Continuation {
+            pending: VecDeque::new(),
+            next: Some(match self.api {
+                Api::Rest => Page::Rest(1),
+                Api::Graphql => Page::Graphql(None),
+            }),
+            lazy: lazy && self.api == Api::Graphql,
+        }
+    }
+
+    /// Fetches releases from `continuation` on until the last one or the limit, returning where
+    /// the releases continue if the limit stopped it.
+    pub async fn fetch_from(
+        &self,
+        mut continuation: Continuation,
+        mut on_page: impl FnMut(usize, Option<usize>),
+    ) -> Result<(Vec<Release>, Option<Continuation>), AppError> {
+        if self.api == Api::Graphql && self.token.is_none() {
+            return Err(AppError::Github(
+                "The GraphQL API requires $GH_ACCESS_TOKEN".to_string(),
+            ));
+        }
+
+        let mut releases = Vec::new();
+        let mut number = 1;
+        let mut pages = None;
+        loop {
+            while let Some(release) = continuation.pending.pop_front() {
+                if self.collect(&mut releases, release) {
+                    let more = !continuation.pending.is_empty() || continuation.next.is_some();
+                    return Ok((releases, more.then_some(continuation)));
+                }
+            }
+            let Some(page) = continuation.next.take() else {
+                return Ok((releases, None));
+            };
+
+            on_page(number, pages);
+            let (batch, next, total) = match page {
+                Page::Rest(page) => self.fetch_rest_page(page).await?,
+                Page::Graphql(cursor) => {
+                    let query = if continuation.lazy {
+                        RELEASE_LIST_QUERY
+                    } else {
+                        RELEASES_QUERY
+                    };
+                    let (mut batch, next, total) = self.fetch_graphql_page(query, cursor).await?;
+                    for release in &mut batch {
+                        release.partial |= continuation.lazy;
+                    }
+                    (batch, next, total)
+                }
+            };
+            if pages.is_none() {
+                pages = self.limit_pages(total);
+            }
+            continuation.pending = batch.into();
+            continuation.next = next;
+            number += 1;
+        }
+    }
+
+    /// Adds the release if it matches the tag filter and returns whether the limit is reached.
+    fn collect(&self, releases: &mut Vec<Release>, release: Release) -> bool {
+        if self
+            .tag_filter
+            .as_ref()
+            .is_none_or(|filter| filter.is_match(&release.tag_name))
+        {
+            releases.push(release);
+        }
+        self.limit.is_some_and(|limit| releases.len() >= limit)
+    }
+
+    /// Number of pages to fetch, at most as many as needed for the limit.
+    fn limit_pages(&self, pages: Option<usize>) -> Option<usize> {
+        match (pages, self.limit) {
+            // filtered out releases may require more pages than that
+            (Some(pages), Some(limit)) if self.tag_filter.is_none() => {
+                Some(pages.min(limit.div_ceil(self.per_page)))
+            }
+            _ => pages,
+        }
+    }
+
+    /// Fetches a page of releases, returning them with the following page, if any, and the number
+    /// of pages.
+    async fn fetch_rest_page(&self, page: usize) -> Result<FetchedPage, AppError> {
+        let url = format!(
+            "{}/repos/{}/{}/releases?per_page={}&page={}",
+            self.api_url, self.owner, self.repo, self.per_page, page
+        );
+        let response = self.send(self.get(&url)).await?.error_for_status()?;
+        let pages = response
+            .headers()
+            .get(LINK)
+            .and_then(|link| link.to_str().ok())
+            .and_then(last_page);
+        let batch = response.json::<Vec<Value>>().await?;
+
+        let next = (batch.len() >= self.per_page).then_some(Page::Rest(page + 1));
+        let releases = batch
+            .into_iter()
+            .map(|raw| {
+                let mut release = Release::deserialize(&raw).map_err(invalid_release)?;
+                release.raw = raw;
+                Ok(release)
+            })
+            .collect::<Result<_, AppError>>()?;
+        Ok((releases, next, pages))
+    }
+
+    /// Fetches a page of releases with a single GraphQL query, see [`Self::fetch_rest_page`].
+    async fn fetch_graphql_page(
+        &self,
+        query: &str,
+        cursor: Option<String>,
+    ) -> Result<FetchedPage, AppError> {
+        let body = json!({
+            "query": query,
+            "variables": {
+                "owner": self.owner,
+                "repo": self.repo,
+                "first": self.per_page,
+                "cursor": cursor,
+            },
+        });
+        let request = self
+            .request(Method::POST, &self.graphql_url, JSON)
+            .json(&body);
+        let response = self
+            .send(request)
+            .await?
+            .error_for_status()?
+            .json::<graphql::Response>()
+            .await?;
+
+        if let Some(error) = response.errors.into_iter().flatten().next() {
+            return Err(AppError::Github(error.message));
+        }
+        let connection = response
+            .data
+            .and_then(|data| data.repository)
+            .ok_or_else(|| AppError::Github("Repository not found".to_string()))?
+            .releases;
+
+        let pages = connection.total_count.div_ceil(self.per_page);
+        let next = match connection.page_info {
+            graphql::PageInfo {
+                has_next_page: true,
+                end_cursor: Some(end_cursor),
+            } => Some(Page::Graphql(Some(end_cursor))),
+            _ => None,
+        };
+        let releases = connection
+            .nodes
+            .into_iter()
+            .map(|raw| {
+                let release = graphql::Release::deserialize(&raw).map_err(invalid_release)?;
+                Ok(Release {
+                    raw,
+                    ..Release::from(release)
+                })
+            })
+            .collect::<Result<_, AppError>>()?;
+        Ok((releases, next, Some(pages)))
+    }
+
+    /// Fetches a single release with all its assets.
+    pub async fn fetch_release(&self, id: i64) -> Result<Release, AppError> {
+        let url = format!(
+            "{}/repos/{}/{}/releases/{}",
+            self.api_url, self.owner, self.repo, id
+        );
+        let raw = self
+            .send(self.get(&url))
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        let mut release = Release::deserialize(&raw).map_err(invalid_release)?;
+        release.raw = raw;
+        Ok(release)
+    }
+
+    /// Ids and tags of the newest releases matching the tag filter and the `ETag` of the
+    /// response, or `None` if nothing changed since the response with `etag`.
+    ///
+    /// Always uses the REST API, GitHub does not count conditional requests answered with
+    /// `304 Not Modified` against the rate limit.
+    pub async fn poll_releases(
+        &self,
+        etag: Option<&str>,
+    ) -> Result<Option<(Vec<(i64, String)>, Option<String>)>, AppError> {
+        let url = format!(
+            "{}/repos/{}/{}/releases?per_page={}",
+            self.api_url, self.owner, self.repo, self.per_page
+        );
+        let mut request = self.get(&url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = self.send(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let releases = response.json::<Vec<Value>>().await?;
+        let releases = releases
+            .iter()
+            .filter_map(|release| Some((release["id"].as_i64()?, release["tag_name"].as_str()?)))
+            .filter(|(_, tag)| {
+                self.tag_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.is_match(tag))
+            })
+            .map(|(id, tag)| (id, tag.to_string()))
+            .collect();
+        Ok(Some((releases, etag)))
+    }
+
+    /// Login of the user the token belongs to, fails with 401 if GitHub does not accept it.
+    pub async fn user(&self) -> Result<String, AppError> {
+        let user = self
+            .send(self.get(&format!("{}/user", self.api_url)))
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        Ok(user["login"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Streams the asset into `file_path`, calling `on_progress` with the downloaded and total
+    /// bytes.
+    pub async fn download_asset(
+        &self,
+        asset: &Asset,
+        file_path: &str,
+        on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<usize, AppError> {
+        let response = self
+            .send_asset_request(asset, HeaderMap::new())
+            .await?
+            .error_for_status()?;
+        write_response(response, file_path, self.write_buffer, on_progress).await
+    }
+
+    /// Downloads a small text asset, like a checksum file, into memory.
+    pub async fn download_text(&self, asset: &Asset) -> Result<String, Error> {
+        self.send_asset_request(asset, HeaderMap::new())
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+
+    /// Downloads the asset unless it did not change since `cached` was received.
+    ///
+    /// Returns the validators of the new download, or `None` if the cached file is current.
+    pub async fn download_asset_if_modified(
+        &self,
+        asset: &Asset,
+        file_path: &str,
+        cached: Option<&Validators>,
+        on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<Option<Validators>, AppError> {
+        let mut headers = HeaderMap::new();
+        if let Some(cached) = cached {
+            let etag = cached.etag.as_deref().and_then(|etag| etag.parse().ok());
+            if let Some(etag) = etag {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            let last_modified = cached.last_modified.as_deref();
+            if let Some(last_modified) = last_modified.and_then(|value| value.parse().ok()) {
+                headers.insert(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = self.send_asset_request(asset, headers).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        write_response(response, file_path, self.write_buffer, on_progress).await?;
+        Ok(Some(validators))
+    }
+
+    /// Requests the asset from its `browser_download_url` if configured, falling back to the API
+    /// when the direct URL is not accessible without a browser session, as for private
+    /// repositories.
+    ///
+    /// Should the token not grant access to the API endpoint, the asset may still be public, so
+    /// the direct URL is tried anonymously before giving up.
+    async fn send_asset_request(
+        &self,
+        asset: &Asset,
+        headers: HeaderMap,
+    ) -> Result<Response, Error> {
+        let direct = !asset.browser_download_url.is_empty();
+        if self.direct_download && direct {
+            let response = self.send_direct_request(asset, headers.clone()).await?;
+            if !is_denied(&response) {
+                return Ok(response);
+            }
+        }
+        let response = self
+            .send(self.asset_request(asset).headers(headers.clone()))
+            .await?;
+        if !self.direct_download
+            && direct
+            && self.token.is_some()
+            && matches!(
+                response.status(),
+                StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+            )
+        {
+            let fallback = self.send_direct_request(asset, headers).await?;
+            if !is_denied(&fallback) {
+                self.notify(format!(
+                    "The token has no access to {} ({}), downloading it from its public URL",
+                    asset.name,
+                    response.status()
+                ));
+                return Ok(fallback);
+            }
+        }
+        Ok(response)
+    }
+
+    /// Requests the asset from its `browser_download_url`, which does not accept API tokens, so
+    /// none is sent along.
+    async fn send_direct_request(
+        &self,
+        asset: &Asset,
+        headers: HeaderMap,
+    ) -> Result<Response, Error> {
+        self.client
+            .get(&asset.browser_download_url)
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .headers(headers)
+            .send()
+            .await
+    }
+
+    fn asset_request(&self, asset: &Asset) -> RequestBuilder {
+        let url = format!(
+            "{}/repos/{}/{}/releases/assets/{}",
+            self.api_url, self.owner, self.repo, asset.id
+        );
+        self.request(Method::GET, &url, "application/octet-stream")
+    }
+
+    /// Sends the request, waiting as long as GitHub asks with `Retry-After` when it is rate limited
+    /// and sending it again, up to [`MAX_RATE_LIMIT_RETRIES`] times.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let mut retries = 0;
+        loop {
+            let Some(attempt) = request.try_clone() else {
+                return request.send().await;
+            };
+            let response = attempt.send().await?;
+            let wait = match retry_after(&response) {
+                Some(wait) if retries < MAX_RATE_LIMIT_RETRIES => wait,
+                _ => return Ok(response),
+            };
+            retries += 1;
+            self.notify(format!("Rate limited, retrying in {}s", wait.as_secs()));
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Sends the message to `notices`, or prints it to stderr without them.
+    fn notify(&self, message: String) {
+        match &self.notices {
+            Some(notices) => {
+                let _ = notices.send(message);
+            }
+            None => eprintln!("{}", message),
+        }
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url, JSON)
+    }
+
+    /// A request to the API with the headers every request sends and the token, if any.
+    fn request(&self, method: Method, url: &str, accept: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, url)
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .header(ACCEPT, accept)
+            .header("X-GitHub-Api-Version", &self.api_version);
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {}", token)),
+            None => request,
+        }
+    }
 }
 
-pub async fn download_asset(
-    owner: &str,
-    repo: &str,
-    token: &str,
-    asset_id: i32,
+/// Where the releases continue after [`GithubClient::fetch_from`] stopped at the limit.
+pub struct Continuation {
+    /// Fetched with the last page, but beyond the limit.
+    pending: VecDeque<Release>,
+    next: Option<Page>,
+    /// Without notes and assets, see [`GithubClient::first_page`].
+    lazy: bool,
+}
+
+enum Page {
+    Rest(usize),
+    /// The cursor of the previous page, `None` for the first.
+    Graphql(Option<String>),
+}
+
+/// Releases of a page, the following page and the number of pages.
+type FetchedPage = (Vec<Release>, Option<Page>, Option<usize>);
+
+/// `ETag` and `Last-Modified` of a downloaded asset, sent along to skip unchanged downloads.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Streams the body into `file_path`, calling `on_progress` with the written and total bytes.
+///
+/// The chunks of the body are only a few KiB each, buffering them saves a blocking write for
+/// every one of them.
+async fn write_response(
+    mut response: reqwest::Response,
     file_path: &str,
-) -> Result<usize, Error> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/releases/assets/{}",
-        owner, repo, asset_id
-    );
-
-    let client = reqwest::Client::new();
-    let auth_header = format!("Bearer {}", token);
-
-    let response = client
-        .get(&url)
-        .header("User-Agent", "request")
-        .header("Authorization", auth_header)
-        .header("Accept", "application/octet-stream")
-        .header("X-GitHub-Api-Version", "2022-11-28")
-        .send()
-        .await?;
-
-    let content = response.bytes().await?;
-
-    let mut file = tokio::fs::File::create(file_path)
-        .await
-        .expect("Failed to create download file!");
-
-    tokio::io::copy(&mut content.as_ref(), &mut file)
-        .await
-        .expect("Failed to copy the downloaded artifact to a local file!");
-
-    Ok(content.len())
+    buffer_size: usize,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<usize, AppError> {
+    let total = response.content_length();
+
+    let file = tokio::fs::File::create(file_path).await?;
+    let mut file = BufWriter::with_capacity(buffer_size, file);
+
+    let mut downloaded = 0;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len();
+        on_progress(downloaded as u64, total);
+    }
+    file.flush().await?;
+
+    Ok(downloaded)
+}
+
+/// Trusts the configured CA certificates in addition to the system's.
+fn http_client(config: &Config) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(config.insecure);
+    for certificate in &config.ca_certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    // like `reqwest::Client::new`, which panics if the TLS backend can not be initialized
+    builder
+        .build()
+        .expect("TLS backend could not be initialized")
+}
+
+fn api_url(host: &str) -> String {
+    if host == GITHUB_HOST {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    }
+}
+
+fn graphql_url(host: &str) -> String {
+    if host == GITHUB_HOST {
+        "https://api.github.com/graphql".to_string()
+    } else {
+        format!("https://{}/api/graphql", host)
+    }
+}
+
+/// Reads the number of pages from a `Link` header like
+/// `<...&page=2>; rel="next", <...&page=5>; rel="last"`.
+fn last_page(link: &str) -> Option<usize> {
+    link.split(',')
+        .find(|part| part.contains(r#"rel="last""#))
+        .and_then(|part| part.split_once("&page="))
+        .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+        .and_then(|page| page.parse().ok())
+}
+
+/// Whether the download host refused the request, as it does for assets of private repositories.
+fn is_denied(response: &Response) -> bool {
+    matches!(
+        response.status(),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+    )
+}
+
+/// How long to wait before repeating a request GitHub refused because of a rate limit, `429` or
+/// `403` for secondary rate limits, both with the seconds in `Retry-After`.
+fn retry_after(response: &Response) -> Option<Duration> {
+    if !matches!(
+        response.status(),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::FORBIDDEN
+    ) {
+        return None;
+    }
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+impl From<graphql::Release> for Release {
+    fn from(release: graphql::Release) -> Self {
+        let assets = release.release_assets;
+        Self {
+            id: release.database_id,
+            tag_name: release.tag_name,
+            body: release.description.unwrap_or_default(),
+            name: release.name,
+            published_at: release.published_at,
+            target_commitish: release
+                .tag_commit
+                .map(|commit| commit.oid)
+                .unwrap_or_default(),
+            reactions: release.reaction_groups.iter().fold(
+                Reactions::default(),
+                |mut reactions, group| {
+                    let count = group.reactors.total_count;
+                    match group.content.as_str() {
+                        "THUMBS_UP" => reactions.thumbs_up = count,
+                        "THUMBS_DOWN" => reactions.thumbs_down = count,
+                        "LAUGH" => reactions.laugh = count,
+                        "HOORAY" => reactions.hooray = count,
+                        "CONFUSED" => reactions.confused = count,
+                        "HEART" => reactions.heart = count,
+                        "ROCKET" => reactions.rocket = count,
+                        "EYES" => reactions.eyes = count,
+                        _ => {}
+                    }
+                    reactions
+                },
+            ),
+            partial: assets.total_count > assets.nodes.len(),
+            assets: assets
+                .nodes
+                .into_iter()
+                .map(|asset| Asset {
+                    name: asset.name,
+                    // not part of the GraphQL schema
+                    label: None,
+                    browser_download_url: asset.download_url,
+                    id: asset.database_id,
+                    content_type: asset.content_type,
+                    size: asset.size,
+                    download_count: asset.download_count,
+                    created_at: asset.created_at,
+                    updated_at: asset.updated_at,
+                })
+                .collect(),
+            raw: Value::Null,
+        }
+    }
+}
+
+fn invalid_release(error: serde_json::Error) -> AppError {
+    AppError::Github(format!("Unexpected release JSON: {}", error))
+}
+
+/// Shapes of the GraphQL response.
+mod graphql {
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct Response {
+        pub data: Option<Data>,
+        pub errors: Option<Vec<Error>>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Error {
+        pub message: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Data {
+        pub repository: Option<Repository>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Repository {
+        pub releases: Connection<serde_json::Value>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Connection<T> {
+        pub total_count: usize,
+        pub page_info: PageInfo,
+        pub nodes: Vec<T>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PageInfo {
+        pub has_next_page: bool,
+        pub end_cursor: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Release {
+        pub database_id: i64,
+        pub tag_name: String,
+        pub name: Option<String>,
+        pub description: Option<String>,
+        pub published_at: Option<DateTime<Utc>>,
+        /// Missing if the tag does not point to a commit.
+        pub tag_commit: Option<Commit>,
+        /// Missing when only the release list was queried.
+        #[serde(default)]
+        pub release_assets: Assets,
+        #[serde(default)]
+        pub reaction_groups: Vec<ReactionGroup>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Commit {
+        pub oid: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ReactionGroup {
+        /// `THUMBS_UP`, `HOORAY`, …
+        pub content: String,
+        pub reactors: Count,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Count {
+        pub total_count: u32,
+    }
+
+    #[derive(Deserialize, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Assets {
+        pub total_count: usize,
+        pub nodes: Vec<Asset>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Asset {
+        pub database_id: i32,
+        pub name: String,
+        pub content_type: String,
+        pub size: u64,
+        #[serde(default)]
+        pub download_count: u64,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+        pub download_url: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Size of the download the write buffer sizes are timed with.
+    const BENCHMARK_SIZE: usize = 64 * 1024 * 1024;
+    /// GitHub delivers downloads in chunks of about this size.
+    const CHUNK_SIZE: usize = 16 * 1024;
+
+    /// Serves a download of `size` bytes, a multiple of `CHUNK_SIZE`, to every request, returns
+    /// its URL.
+    async fn serve(size: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0; 1024];
+                    let _ = stream.read(&mut request).await;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        size
+                    );
+                    // the client may hang up early, e.g. when it can not write the file
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let chunk = [0x5a; CHUNK_SIZE];
+                    for _ in 0..size / CHUNK_SIZE {
+                        if stream.write_all(&chunk).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{}/asset.apk", address)
+    }
+
+    #[test]
+    fn deserializes_minimal_releases() {
+        let release: Release = serde_json::from_value(json!({
+            "id": 1,
+            "tag_name": "v1.0.0",
+            "assets": [],
+        }))
+        .unwrap();
+
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(release.body, "");
+        assert_eq!(release.name, None);
+        assert_eq!(release.published_at, None);
+        assert!(release.assets.is_empty());
+        assert!(release.reactions.counts().is_empty());
+    }
+
+    #[test]
+    fn deserializes_nulls_and_unknown_fields() {
+        let release: Release = serde_json::from_value(json!({
+            "id": 1,
+            "tag_name": "v1.0.0",
+            "body": null,
+            "name": null,
+            "published_at": null,
+            "target_commitish": null,
+            "reactions": null,
+            "discussion_url": "https://github.com/owner/repo/discussions/1",
+            "assets": [{
+                "id": 2,
+                "name": "app.apk",
+                "label": null,
+                "browser_download_url": null,
+                "content_type": null,
+                "size": null,
+                "download_count": null,
+                "created_at": null,
+                "updated_at": null,
+                "digest": "sha256:0000",
+            }],
+        }))
+        .unwrap();
+
+        assert_eq!(release.body, "");
+        assert_eq!(release.target_commitish, "");
+        let asset = &release.assets[0];
+        assert_eq!(asset.display_name(), "app.apk");
+        assert_eq!(asset.browser_download_url, "");
+        assert_eq!(asset.size, 0);
+        assert_eq!(asset.created_at, DateTime::<Utc>::default());
+    }
+
+    #[test]
+    fn deserializes_null_assets() {
+        let release: Release = serde_json::from_value(json!({
+            "id": 1,
+            "tag_name": "v1.0.0",
+            "assets": null,
+        }))
+        .unwrap();
+
+        assert!(release.assets.is_empty());
+        assert!(release.apk_asset().is_none());
+    }
+
+    #[test]
+    fn requires_the_tag_and_ids() {
+        assert!(serde_json::from_value::<Release>(json!({ "id": 1 })).is_err());
+        assert!(serde_json::from_value::<Release>(json!({ "tag_name": "v1.0.0" })).is_err());
+        let asset = json!({ "id": 1, "tag_name": "v1.0.0", "assets": [{ "name": "app.apk" }] });
+        assert!(serde_json::from_value::<Release>(asset).is_err());
+    }
+
+    #[tokio::test]
+    async fn write_response_fails_on_unwritable_path() {
+        let url = serve(CHUNK_SIZE).await;
+        let response = reqwest::get(&url).await.unwrap();
+        let path = std::env::temp_dir().join("github_install-missing/asset.apk");
+
+        let result = write_response(response, &path.to_string_lossy(), 1024, |_, _| {}).await;
+
+        assert!(matches!(result, Err(AppError::Io(_))));
+    }
+
+    /// Times the download with several buffer sizes, the best of a few rounds each, run it with
+    /// `cargo test --release write_buffer -- --ignored --nocapture`.
+    #[tokio::test]
+    #[ignore]
+    async fn write_buffer_throughput() {
+        let url = serve(BENCHMARK_SIZE).await;
+        let path =
+            std::env::temp_dir().join(format!("github_install-{}.bench", std::process::id()));
+        for kibibytes in [8, 64, 256, 1024, 4096] {
+            let mut best = Duration::MAX;
+            for _ in 0..5 {
+                // truncating the file of the previous round would be timed along
+                let _ = std::fs::remove_file(&path);
+                let response = reqwest::get(&url).await.unwrap();
+                let start = Instant::now();
+                let written = write_response(
+                    response,
+                    &path.to_string_lossy(),
+                    kibibytes * 1024,
+                    |_, _| {},
+                )
+                .await
+                .unwrap();
+                best = best.min(start.elapsed());
+                assert_eq!(written, BENCHMARK_SIZE);
+            }
+            println!("{:>4} KiB buffer: {:?}", kibibytes, best);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Times downloading the first asset of the newest release of `$BENCH_REPO` through the API
+    /// and from its `browser_download_url`, the best of a few rounds each. It needs network access,
+    /// run it with `BENCH_REPO=owner/repo cargo test --release direct_download -- --ignored
+    /// --nocapture`, `$GH_ACCESS_TOKEN` is used for the API if set.
+    #[tokio::test]
+    #[ignore]
+    async fn direct_download_throughput() {
+        let repo: crate::config::Repo = std::env::var("BENCH_REPO")
+            .expect("BENCH_REPO=owner/repo")
+            .parse()
+            .unwrap();
+        let mut config = Config::defaults().for_repo(&repo);
+        config.token = std::env::var("GH_ACCESS_TOKEN").ok();
+        config.limit = Some(1);
+        let releases = GithubClient::new(&config)
+            .fetch_releases(|_, _| {})
+            .await
+            .unwrap();
+        let asset = &releases[0].assets[0];
+        let path =
+            std::env::temp_dir().join(format!("github_install-{}.direct", std::process::id()));
+        for direct_download in [false, true] {
+            config.direct_download = direct_download;
+            let github = GithubClient::new(&config);
+            let mut best = Duration::MAX;
+            for _ in 0..3 {
+                let start = Instant::now();
+                github
+                    .download_asset(asset, &path.to_string_lossy(), |_, _| {})
+                    .await
+                    .unwrap();
+                best = best.min(start.elapsed());
+            }
+            let way = if direct_download { "direct" } else { "API" };
+            println!(
+                "{} ({} bytes) via {}: {:?}",
+                asset.name, asset.size, way, best
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,217 @@
+use std::env;
+use std::str::FromStr;
+use tokio::process::Command;
+
+/// Values the placeholders of a post-install command are replaced with.
+pub struct Values<'a> {
+    pub package: &'a str,
+    pub tag: &'a str,
+    pub asset: &'a str,
+    pub serial: &'a str,
+}
+
+/// Shell command run on this machine after an install succeeded, with placeholders like
+/// `{package}`, `{tag}`, `{asset}`, `{serial}` and `{env:NAME}`.
+#[derive(Clone)]
+pub struct PostInstall(String);
+
+const PLACEHOLDERS: [&str; 4] = ["package", "tag", "asset", "serial"];
+
+impl FromStr for PostInstall {
+    type Err = String;
+
+    fn from_str(command: &str) -> Result<Self, String> {
+        let invalid =
+            |reason: String| format!("Invalid post-install command '{}': {}", command, reason);
+        for placeholder in placeholders(command).map_err(invalid)? {
+            let known = match placeholder.strip_prefix("env:") {
+                Some(name) => !name.is_empty(),
+                None => PLACEHOLDERS.contains(&placeholder),
+            };
+            if !known {
+                return Err(invalid(format!(
+                    "unknown placeholder {{{}}}, known are {{package}}, {{tag}}, {{asset}}, \
+                     {{serial}} and {{env:NAME}}",
+                    placeholder
+                )));
+            }
+        }
+        Ok(Self(command.to_string()))
+    }
+}
+
+impl PostInstall {
+    /// Fills in the placeholders, each value quoted as a single shell word. Fails if a referenced
+    /// environment variable is not set, an empty one is substituted as is.
+    pub fn expand(&self, values: &Values) -> Result<String, String> {
+        let mut command = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            // validated on parsing, so the brace is closed
+            let end = start + rest[start..].find('}').unwrap_or(rest.len() - start);
+            let name = &rest[start + 1..end];
+            let value = match name {
+                "package" => values.package.to_string(),
+                "tag" => values.tag.to_string(),
+                "asset" => values.asset.to_string(),
+                "serial" => values.serial.to_string(),
+                _ => {
+                    let variable = name.strip_prefix("env:").unwrap_or(name);
+                    env::var(variable).map_err(|_| {
+                        format!(
+                            "${} is not set, {{{}}} can not be filled in",
+                            variable, name
+                        )
+                    })?
+                }
+            };
+            command += &rest[..start];
+            command += &quote(&value);
+            rest = &rest[end + 1..];
+        }
+        command += rest;
+        Ok(command)
+    }
+
+    /// Expands and runs the command, failing with its error output if it exits unsuccessfully.
+    pub async fn run(&self, values: &Values<'_>) -> Result<(), String> {
+        let command = self.expand(values)?;
+        let output = shell(&command)
+            .output()
+            .await
+            .map_err(|error| format!("Could not run '{}'! {}", command, error))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "'{}' failed ({})! {}",
+                command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+/// Runs the commands one after another, stopping at the first that fails.
+pub async fn run_all(commands: &[PostInstall], values: &Values<'_>) -> Result<(), String> {
+    for command in commands {
+        command.run(values).await?;
+    }
+    Ok(())
+}
+
+/// Names between the braces of the command.
+fn placeholders(command: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = command;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("'}' without '{'".to_string());
+        }
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or_else(|| "'{' without '}'".to_string())?;
+        names.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+    Ok(names)
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+/// Quotes the value so the shell passes it on unchanged, e.g. a tag containing `;`.
+#[cfg(unix)]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: Values = Values {
+        package: "com.example.app",
+        tag: "v1.2.0",
+        asset: "app-release.apk",
+        serial: "emulator-5554",
+    };
+
+    fn expand(command: &str) -> Result<String, String> {
+        command.parse::<PostInstall>()?.expand(&VALUES)
+    }
+
+    #[test]
+    fn expands_the_values() {
+        assert_eq!(expand("echo {tag}").unwrap(), "echo 'v1.2.0'");
+        assert_eq!(expand("echo {asset}").unwrap(), "echo 'app-release.apk'");
+        assert_eq!(
+            expand("adb -s {serial} shell am start {package}").unwrap(),
+            "adb -s 'emulator-5554' shell am start 'com.example.app'"
+        );
+    }
+
+    #[test]
+    fn quotes_the_values() {
+        let values = Values {
+            tag: "v1'; rm -rf ~",
+            ..VALUES
+        };
+        let command: PostInstall = "echo {tag}".parse().unwrap();
+        #[cfg(unix)]
+        assert_eq!(command.expand(&values).unwrap(), r"echo 'v1'\''; rm -rf ~'");
+        #[cfg(windows)]
+        assert_eq!(command.expand(&values).unwrap(), "echo \"v1'; rm -rf ~\"");
+    }
+
+    #[test]
+    fn expands_set_environment_variables() {
+        env::set_var("GITHUB_INSTALL_TEST_SET", "channel");
+        env::set_var("GITHUB_INSTALL_TEST_EMPTY", "");
+        assert_eq!(
+            expand("notify {env:GITHUB_INSTALL_TEST_SET} {tag}").unwrap(),
+            "notify 'channel' 'v1.2.0'"
+        );
+        assert_eq!(
+            expand("notify {env:GITHUB_INSTALL_TEST_EMPTY}").unwrap(),
+            "notify ''"
+        );
+    }
+
+    #[test]
+    fn fails_on_missing_environment_variables() {
+        env::remove_var("GITHUB_INSTALL_TEST_MISSING");
+        let error = expand("notify {env:GITHUB_INSTALL_TEST_MISSING}").unwrap_err();
+        assert!(
+            error.contains("$GITHUB_INSTALL_TEST_MISSING is not set"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert!("echo {version}".parse::<PostInstall>().is_err());
+        assert!("echo {env:}".parse::<PostInstall>().is_err());
+        assert!("echo {tag".parse::<PostInstall>().is_err());
+        assert!("echo tag}".parse::<PostInstall>().is_err());
+    }
+}
@@ -0,0 +1,692 @@
+use adb_client::AdbTcpConnection;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fmt, process};
+use tokio::sync::mpsc;
+use tokio::task;
+use tokio::time::{sleep, timeout};
+use zip::ZipArchive;
+
+use crate::adb;
+use crate::apk::{self, ApkInfo};
+use crate::cache::Cache;
+use crate::checksums;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::format_size;
+use crate::github::{Asset, GithubClient};
+
+/// Start of the names of APKs downloaded into the temporary directory for a single install.
+const TEMPORARY_PREFIX: &str = "github_install-";
+/// Directory APKs are pushed to before `pm install` reads them from there.
+const DEVICE_APK_DIR: &str = "/data/local/tmp";
+
+/// ABIs that show up in the names of split APKs, e.g. `app-arm64-v8a-release.apk`.
+const KNOWN_ABIS: [&str; 4] = ["arm64-v8a", "armeabi-v7a", "x86_64", "x86"];
+
+/// Older ADB versions count the bytes of a push in signed 32 bits and drop the connection past it.
+const PUSH_SIZE_LIMIT: u64 = i32::MAX as u64;
+
+/// Local file header signature every zip archive, and so every APK, starts with.
+const ZIP_SIGNATURE: &[u8; 4] = b"PK\x03\x04";
+
+/// What an install is busy with, reported along with the bytes done and in total.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Downloading,
+    Pushing,
+    Installing,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Downloading => "Downloading",
+            Phase::Pushing => "Pushing",
+            Phase::Installing => "Installing",
+        })
+    }
+}
+
+/// Reads from `inner`, telling `on_read` how many bytes were read so far.
+struct Counting<R, F> {
+    inner: R,
+    read: u64,
+    on_read: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for Counting<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        (self.on_read)(self.read);
+        Ok(read)
+    }
+}
+
+/// How an installed version of the app may be replaced.
+#[derive(Default, Copy, Clone)]
+pub struct Replace {
+    /// Uninstall it first, which is required when its signature differs.
+    pub uninstall_first: bool,
+    /// Replace it even if it is newer (`pm install -d`).
+    pub allow_downgrade: bool,
+}
+
+/// Connects to the configured remote device, selects the device and sets up the port mappings.
+pub fn open_session(config: &Config) -> Result<adb::Device, AppError> {
+    let mut serial = config.serial.as_deref();
+    if let Some(address) = &config.connect {
+        adb::ensure_server()?;
+        adb::connect(address)?;
+        // a device connected over the network is named after its address
+        serial = serial.or(Some(address));
+    }
+
+    let device = adb::select_device(serial)?;
+    open_port_mappings(config, &device.serial)?;
+    Ok(device)
+}
+
+/// Sets up the configured port mappings on the device, removing them again if one fails.
+pub fn open_port_mappings(config: &Config, serial: &str) -> Result<(), AppError> {
+    for (index, mapping) in config.port_mappings.iter().enumerate() {
+        if let Err(error) = adb::open_port_mapping(serial, mapping) {
+            for opened in &config.port_mappings[..index] {
+                let _ = adb::close_port_mapping(serial, opened);
+            }
+            return Err(AppError::PortMapping(
+                mapping.to_string(),
+                error.to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the configured port mappings from the device, returning why some could not be.
+pub fn close_port_mappings(config: &Config, serial: &str) -> Vec<String> {
+    config
+        .port_mappings
+        .iter()
+        .filter_map(|mapping| {
+            adb::close_port_mapping(serial, mapping)
+                .err()
+                .map(|error| format!("Could not remove {}! {}", mapping, error))
+        })
+        .collect()
+}
+
+/// Downloads the asset, or takes it from the cache, and installs it on the device for the
+/// configured user. Returns the path of the installed APK.
+///
+/// The download is checked against `checksum`, the SHA-256 listed for it in the checksum
+/// manifest of the release, if there is one.
+///
+/// `replace` tells how an installed version of the app may be replaced. `on_progress` gets the
+/// phase with the bytes done and in total, `on_warning` is told about retries after the device
+/// dropped off and other problems the install continues after.
+#[allow(clippy::too_many_arguments)]
+pub async fn install_asset(
+    github: &GithubClient,
+    config: &Config,
+    serial: &str,
+    asset: &Asset,
+    checksum: Option<&str>,
+    replace: Replace,
+    mut on_progress: impl FnMut(Phase, u64, Option<u64>),
+    on_warning: impl FnMut(String),
+) -> Result<PathBuf, AppError> {
+    // rather than after a download that can not be installed
+    adb::check_device(serial)?;
+    let path = download(github, config, asset, checksum, |done, total| {
+        on_progress(Phase::Downloading, done, total)
+    })
+    .await?;
+    if let Err(error) = install_apk(config, serial, &path, replace, on_progress, on_warning).await {
+        if is_temporary(&path) {
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+        return Err(error);
+    }
+    Ok(path)
+}
+
+/// A path in the temporary directory for downloading the asset to, which no other download of
+/// this or another running instance uses.
+pub fn temporary_path(asset: &Asset) -> PathBuf {
+    static DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+    env::temp_dir().join(format!(
+        "{}{}-{}-{}.apk",
+        TEMPORARY_PREFIX,
+        process::id(),
+        DOWNLOADS.fetch_add(1, Ordering::Relaxed),
+        asset.id
+    ))
+}
+
+/// Whether the APK was downloaded to a [`temporary_path`] rather than into the cache.
+pub fn is_temporary(path: &Path) -> bool {
+    path.parent() == Some(env::temp_dir().as_path())
+        && path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(TEMPORARY_PREFIX))
+}
+
+/// Downloads the asset, or takes it from the cache, and copies it to `<push_dir>/<asset>` on the
+/// device without installing it. Returns the path on the device.
+pub async fn push_asset(
+    github: &GithubClient,
+    config: &Config,
+    serial: &str,
+    asset: &Asset,
+    checksum: Option<&str>,
+    on_warning: impl FnMut(String),
+) -> Result<String, AppError> {
+    adb::ensure_server()?;
+    let path = download(github, config, asset, checksum, |_, _| {}).await?;
+    let device_path = format!("{}/{}", config.push_dir.trim_end_matches('/'), asset.name);
+    let pushed = push(serial, &path, &device_path, |_| {}, on_warning).await;
+    if is_temporary(&path) {
+        tokio::fs::remove_file(&path).await?;
+    }
+    pushed.map(|()| device_path)
+}
+
+/// Downloads the asset, or takes it from the cache, and checks it against `checksum`.
+async fn download(
+    github: &GithubClient,
+    config: &Config,
+    asset: &Asset,
+    checksum: Option<&str>,
+    on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf, AppError> {
+    let path = if config.cache {
+        Cache::new(config).fetch(github, asset, on_progress).await?
+    } else {
+        let path = temporary_path(asset);
+        github
+            .download_asset(asset, &path.to_string_lossy(), on_progress)
+            .await?;
+        path
+    };
+    if let Some(expected) = checksum {
+        checksums::verify(&path, &asset.name, expected)?;
+    }
+    Ok(path)
+}
+
+/// Copies the file to `device_path`, retried when the device drops off. `on_progress` gets the
+/// bytes sent so far.
+///
+/// The copy runs on a blocking thread, so the caller keeps being polled, e.g. to redraw.
+async fn push(
+    serial: &str,
+    path: &Path,
+    device_path: &str,
+    mut on_progress: impl FnMut(u64),
+    mut on_warning: impl FnMut(String),
+) -> Result<(), AppError> {
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let (warning_tx, mut warning_rx) = mpsc::unbounded_channel();
+    let serial = serial.to_string();
+    let path = path.to_path_buf();
+    let device_path = device_path.to_string();
+    let mut pushing = task::spawn_blocking(move || {
+        let on_retry = |message| {
+            let _ = warning_tx.send(message);
+        };
+        adb::with_reconnect(on_retry, || {
+            // a fresh connection for every attempt, the previous one is gone with the device
+            let mut connection = AdbTcpConnection::new(Ipv4Addr::from([127, 0, 0, 1]), 5037)
+                .map_err(|error| AppError::Adb(error.to_string()))?;
+            let mut input = Counting {
+                inner: File::open(&path)?,
+                read: 0,
+                on_read: |read| {
+                    let _ = progress_tx.send(read);
+                },
+            };
+            connection
+                .send(Some(&serial), &mut input, &device_path)
+                .map_err(|error| AppError::Adb(format!("Could not send apk to device! {}", error)))
+        })
+    });
+    let result = loop {
+        tokio::select! {
+            result = &mut pushing => break result,
+            Some(sent) = progress_rx.recv() => on_progress(sent),
+            Some(message) = warning_rx.recv() => on_warning(message),
+        }
+    };
+    while let Ok(message) = warning_rx.try_recv() {
+        on_warning(message);
+    }
+    result.map_err(|error| AppError::Adb(error.to_string()))?
+}
+
+/// Installs an APK file on the device for the configured user.
+///
+/// Pushing the APK and the preparing commands are retried when the device drops off, the
+/// install itself is not as it may have succeeded already.
+pub async fn install_apk(
+    config: &Config,
+    serial: &str,
+    path: &Path,
+    replace: Replace,
+    mut on_progress: impl FnMut(Phase, u64, Option<u64>),
+    mut on_warning: impl FnMut(String),
+) -> Result<(), AppError> {
+    check_apk(path)?;
+    // an unauthorized device only fails once the push is attempted, with a cryptic error
+    adb::check_device(serial)?;
+
+    let apk = apk::inspect(path)?;
+    if let Some(min) = apk.min_sdk {
+        let serial = serial.to_string();
+        let device = adb::with_reconnect_async(&mut on_warning, move || adb::sdk(Some(&serial)))
+            .await
+            .map_err(|error| AppError::Adb(error.to_string()))?;
+        if let Some(device) = device.filter(|device| *device < min) {
+            return Err(AppError::OlderSdk { min, device });
+        }
+    }
+    if replace.uninstall_first {
+        let installed = adb::with_reconnect_async(&mut on_warning, {
+            let (serial, package) = (serial.to_string(), apk.package.clone());
+            move || adb::version_code(Some(&serial), &package)
+        })
+        .await
+        .map_err(|error| AppError::Adb(error.to_string()))?;
+        if installed.is_some() {
+            adb::with_reconnect_async(&mut on_warning, {
+                let (serial, package) = (serial.to_string(), apk.package.clone());
+                move || adb::shell(Some(&serial), &["pm", "uninstall", &package])
+            })
+            .await
+            .map_err(|error| AppError::Adb(error.to_string()))?;
+            on_warning(format!("Uninstalled {} and its data", apk.package));
+        } else {
+            on_warning(format!(
+                "{} is not installed, nothing to uninstall",
+                apk.package
+            ));
+        }
+    } else {
+        if config.check_signature {
+            let installed = adb::with_reconnect_async(&mut on_warning, {
+                let (serial, package) = (serial.to_string(), apk.package.clone());
+                move || adb::installed_signatures(Some(&serial), &package)
+            })
+            .await
+            .map_err(|error| AppError::Adb(error.to_string()))?;
+            check_signature(&apk, &installed)?;
+        }
+        if !replace.allow_downgrade {
+            let installed = adb::with_reconnect_async(&mut on_warning, {
+                let (serial, package) = (serial.to_string(), apk.package.clone());
+                move || adb::version_code(Some(&serial), &package)
+            })
+            .await
+            .map_err(|error| AppError::Adb(error.to_string()))?;
+            check_downgrade(&apk, installed)?;
+        }
+    }
+
+    let size = path.metadata()?.len();
+    if config.warn_apk_size.is_some_and(|limit| size > limit) {
+        on_warning(format!(
+            "The APK is {}, pushing it may fail with older ADB versions",
+            format_size(size)
+        ));
+    }
+
+    adb::ensure_server()?;
+    // unique, so installs to the same device can not overwrite or remove each other's APK
+    let device_path = format!(
+        "{}/github_install-{}-{}.apk",
+        DEVICE_APK_DIR,
+        std::process::id(),
+        chrono::Utc::now().timestamp_micros()
+    );
+    let pushed = push(
+        serial,
+        path,
+        &device_path,
+        |sent| on_progress(Phase::Pushing, sent, Some(size)),
+        &mut on_warning,
+    )
+    .await;
+    // large APKs can exceed what the push of older ADB versions copes with, streaming the APK
+    // into `pm install` needs no copy on the device
+    let streamed = match pushed {
+        Ok(()) => false,
+        Err(error) if exceeds_transfer_limit(&error, size) => {
+            on_warning(format!("{} Streaming it to the installer instead", error));
+            true
+        }
+        Err(error) => return Err(error),
+    };
+
+    let mut args = vec!["-r".to_string()];
+    if replace.allow_downgrade {
+        args.push("-d".to_string());
+    }
+    args.extend(["--user".to_string(), config.user_id.to_string()]);
+    on_progress(Phase::Installing, 0, None);
+    let install_serial = serial.to_string();
+    let apk_path = path.to_path_buf();
+    let pushed_path = device_path.clone();
+    let install_timeout = config.install_timeout;
+    let install = task::spawn_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        if streamed {
+            adb::install_streamed(&install_serial, &apk_path, &args, install_timeout)
+        } else {
+            let command = [&["pm", "install"], &args[..], &[pushed_path.as_str()]].concat();
+            adb::shell_with_timeout(Some(&install_serial), &command, install_timeout)
+        }
+    });
+    // after a timeout `pm install` may still be reading the pushed APK
+    let output = timeout(config.install_timeout, install)
+        .await
+        .map_err(|_| AppError::InstallTimedOut(config.install_timeout.as_secs()))?
+        .map_err(|error| AppError::Install(error.to_string()))?;
+    if !streamed && !config.keep_device_apk {
+        match adb::shell(Some(serial), &["rm", "-f", &device_path]) {
+            Ok(_) => on_warning(format!("Removed {} from the device", device_path)),
+            Err(error) => on_warning(format!(
+                "Could not remove {} from the device! {}",
+                device_path, error
+            )),
+        }
+    }
+    let output = output.map_err(|error| {
+        if streamed {
+            AppError::Install(format!(
+                "Could not transfer the APK ({}) to the device! {} Try a USB connection or \
+                     a newer ADB server (platform-tools)",
+                format_size(size),
+                error
+            ))
+        } else {
+            AppError::Install(error.to_string())
+        }
+    })?;
+
+    if output.contains("INSTALL_FAILED_USER_RESTRICTED") {
+        return Err(AppError::UserRestricted(config.user_id));
+    }
+    if output.contains("INSTALL_FAILED_NO_MATCHING_ABIS")
+        || output.contains("INSTALL_FAILED_CPU_ABI_INCOMPATIBLE")
+    {
+        return Err(AppError::IncompatibleAbi(output.trim().to_string()));
+    }
+    if !output.contains("Success") {
+        return Err(AppError::Install(output.trim().to_string()));
+    }
+
+    Ok(())
+}
+
+/// Whether the push failed because the APK is larger than the ADB transfer copes with, rather than
+/// e.g. because the device is full or gone.
+fn exceeds_transfer_limit(error: &AppError, size: u64) -> bool {
+    let message = error.to_string().to_lowercase();
+    let too_large = ["too large", "too big", "file size", "message too long"]
+        .iter()
+        .any(|pattern| message.contains(pattern));
+    let dropped = [
+        "connection reset",
+        "broken pipe",
+        "unexpected end of file",
+        "failed to fill whole buffer",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern));
+    too_large || (dropped && size > PUSH_SIZE_LIMIT)
+}
+
+/// Installs the APK built for the ABI the device prefers, falling back to the next supported ABI
+/// and finally a universal APK whenever the install fails because of the ABI.
+///
+/// Returns the path and the name of the asset that got installed.
+#[allow(clippy::too_many_arguments)]
+pub async fn install_auto_abi(
+    github: &GithubClient,
+    config: &Config,
+    serial: &str,
+    assets: &[Asset],
+    checksums: &HashMap<String, String>,
+    replace: Replace,
+    mut on_progress: impl FnMut(Phase, u64, Option<u64>),
+    mut on_warning: impl FnMut(String),
+) -> Result<(PathBuf, String), AppError> {
+    let device = serial.to_string();
+    let abis = adb::with_reconnect_async(&mut on_warning, move || adb::abis(Some(&device)))
+        .await
+        .map_err(|error| AppError::Adb(error.to_string()))?;
+
+    let mut last_error = AppError::IncompatibleAbi(abis.join(", "));
+    for asset in abi_candidates(assets, &abis) {
+        let result = install_asset(
+            github,
+            config,
+            serial,
+            asset,
+            checksums.get(&asset.name).map(String::as_str),
+            replace,
+            &mut on_progress,
+            &mut on_warning,
+        )
+        .await;
+        match result {
+            Ok(path) => return Ok((path, asset.name.clone())),
+            Err(error @ AppError::IncompatibleAbi(_)) => last_error = error,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(last_error)
+}
+
+/// APK assets to try in order: those for each ABI of the device, then the ones for no specific ABI.
+fn abi_candidates<'a>(assets: &'a [Asset], abis: &[String]) -> Vec<&'a Asset> {
+    let apks: Vec<&Asset> = assets.iter().filter(|a| a.name.ends_with(".apk")).collect();
+    // `x86` is contained in `x86_64`, so the longest matching ABI wins
+    let abi_of = |asset: &Asset| {
+        KNOWN_ABIS
+            .iter()
+            .filter(|abi| asset.name.contains(*abi))
+            .max_by_key(|abi| abi.len())
+            .copied()
+    };
+
+    let mut candidates: Vec<&Asset> = abis
+        .iter()
+        .flat_map(|abi| {
+            apks.iter()
+                .filter(move |asset| abi_of(asset) == Some(abi.as_str()))
+                .copied()
+        })
+        .collect();
+    candidates.extend(apks.iter().filter(|asset| abi_of(asset).is_none()));
+    candidates
+}
+
+/// Launches the app of the installed APK and saves a screenshot of the device into `dir`.
+pub async fn capture_screenshot(
+    config: &Config,
+    serial: &str,
+    apk_path: &Path,
+    dir: &Path,
+) -> Result<PathBuf, AppError> {
+    let apk = apk::inspect(apk_path)?;
+    adb::launch(Some(serial), &apk.package).map_err(|error| AppError::Adb(error.to_string()))?;
+    sleep(config.screenshot_delay).await;
+
+    let serial = serial.to_string();
+    let png = task::spawn_blocking(move || adb::exec(Some(&serial), &["screencap", "-p"]))
+        .await
+        .map_err(|error| AppError::Adb(error.to_string()))?
+        .map_err(|error| AppError::Adb(error.to_string()))?;
+
+    let path = dir.join(format!(
+        "{}-{}.png",
+        apk.package,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::write(&path, png).await?;
+    Ok(path)
+}
+
+/// Moves the installed APK to `<dir>/<repo>-<tag>.apk` if it should be kept, removes it otherwise.
+///
+/// Cached APKs are copied and stay in the cache.
+pub async fn keep_apk(
+    config: &Config,
+    apk_path: &Path,
+    tag_name: &str,
+) -> io::Result<Option<PathBuf>> {
+    let temporary = is_temporary(apk_path);
+    let Some(dir) = &config.keep_apk_dir else {
+        if temporary {
+            tokio::fs::remove_file(apk_path).await?;
+        }
+        return Ok(None);
+    };
+
+    tokio::fs::create_dir_all(dir).await?;
+    let path = dir.join(format!(
+        "{}-{}.apk",
+        config.repo,
+        tag_name.replace('/', "-")
+    ));
+    if !temporary {
+        tokio::fs::copy(apk_path, &path).await?;
+    } else if tokio::fs::rename(apk_path, &path).await.is_err() {
+        // renaming fails across file systems
+        tokio::fs::copy(apk_path, &path).await?;
+        tokio::fs::remove_file(apk_path).await?;
+    }
+    Ok(Some(path))
+}
+
+/// Fails if the app is installed already but signed with a different certificate, in which case
+/// a `pm install -r` would fail.
+fn check_signature(apk: &ApkInfo, installed: &[String]) -> Result<(), AppError> {
+    let Some(hash) = &apk.certificate_hash else {
+        // only v1 signed, nothing to compare
+        return Ok(());
+    };
+
+    if installed.is_empty() || installed.contains(hash) {
+        Ok(())
+    } else {
+        Err(AppError::SignatureMismatch(
+            apk.package.clone(),
+            apk.schemes.to_string(),
+        ))
+    }
+}
+
+/// Fails if the device has a newer version of the app than the APK.
+fn check_downgrade(apk: &ApkInfo, installed: Option<u64>) -> Result<(), AppError> {
+    match (installed, apk.version_code) {
+        (Some(installed), Some(version_code)) if version_code < installed => {
+            Err(AppError::Downgrade {
+                package: apk.package.clone(),
+                installed,
+                apk: version_code,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks the content of the file instead of trusting the asset name, so an error page that was
+/// downloaded in place of the APK is never pushed to the device.
+fn check_apk(path: &Path) -> Result<(), AppError> {
+    let mut head = Vec::new();
+    File::open(path)?.take(512).read_to_end(&mut head)?;
+    if !head.starts_with(ZIP_SIGNATURE) {
+        let text = String::from_utf8_lossy(&head);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        let reason = if head.is_empty() {
+            "it is empty".to_string()
+        } else if text.starts_with('<') {
+            "it looks like an HTML or XML page, e.g. an error page downloaded instead".to_string()
+        } else if text.starts_with('{') {
+            "it looks like JSON, e.g. an error response of the API".to_string()
+        } else {
+            "it does not start with a zip signature".to_string()
+        };
+        return Err(AppError::NotAnApk(reason));
+    }
+
+    let archive = ZipArchive::new(File::open(path)?).map_err(|error| {
+        AppError::NotAnApk(format!("it is not a readable zip archive ({})", error))
+    })?;
+    if archive.index_for_name("AndroidManifest.xml").is_some() {
+        Ok(())
+    } else if archive
+        .index_for_name("base/manifest/AndroidManifest.xml")
+        .is_some()
+    {
+        Err(AppError::NotAnApk(
+            "it is an Android App Bundle (.aab), which has to be turned into APKs first"
+                .to_string(),
+        ))
+    } else {
+        Err(AppError::NotAnApk(
+            "the zip archive has no AndroidManifest.xml".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LARGE: u64 = 3 * 1024 * 1024 * 1024;
+    const SMALL: u64 = 50 * 1024 * 1024;
+
+    fn push_error(message: &str) -> AppError {
+        AppError::Adb(format!("Could not send apk to device! {}", message))
+    }
+
+    #[test]
+    fn streams_apks_exceeding_the_transfer_limit() {
+        assert!(exceeds_transfer_limit(&push_error("file too large"), SMALL));
+        assert!(exceeds_transfer_limit(
+            &push_error("Broken pipe (os error 32)"),
+            LARGE
+        ));
+        assert!(exceeds_transfer_limit(
+            &push_error("Connection reset by peer (os error 104)"),
+            LARGE
+        ));
+    }
+
+    #[test]
+    fn passes_other_push_errors_through() {
+        assert!(!exceeds_transfer_limit(
+            &push_error("Broken pipe (os error 32)"),
+            SMALL
+        ));
+        assert!(!exceeds_transfer_limit(
+            &push_error("No space left on device"),
+            LARGE
+        ));
+        assert!(!exceeds_transfer_limit(
+            &push_error("device offline"),
+            LARGE
+        ));
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(!exceeds_transfer_limit(&AppError::Io(denied), LARGE));
+    }
+}
@@ -0,0 +1,301 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Logical actions that can be bound to keys.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Next,
+    Previous,
+    Unselect,
+    Install,
+    CleanInstall,
+    BroadcastInstall,
+    Undo,
+    Top,
+    Bottom,
+    SwitchUser,
+    SwitchDevice,
+    CheckDevice,
+    Search,
+    SearchNotes,
+    ToggleInstallable,
+    MarkDiffBase,
+    ToggleView,
+    ToggleStatusSource,
+    DownloadAll,
+    CopyInstallCommand,
+    ShowJson,
+    DeleteCached,
+    ClearCache,
+    Refresh,
+    ToggleInventory,
+    FilterLog,
+    Enqueue,
+    RunQueue,
+    WatchLogcat,
+    PauseLogcat,
+    ClearLogcat,
+    ExportReport,
+    ExpandInfo,
+    TogglePin,
+    JumpToTag,
+    PushOnly,
+    CycleSort,
+    ToggleActions,
+    PickAsset,
+    CopyNotes,
+    PickDevice,
+    Quit,
+}
+
+impl Action {
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Next => &["Down", "j"],
+            Action::Previous => &["Up", "k"],
+            Action::Unselect => &["Left", "h"],
+            Action::Install => &["Right", "l", "Enter"],
+            Action::CleanInstall => &["R"],
+            Action::BroadcastInstall => &["B"],
+            Action::Undo => &["u"],
+            Action::Top => &["g"],
+            Action::Bottom => &["G"],
+            Action::SwitchUser => &["p"],
+            Action::SwitchDevice => &["d"],
+            Action::CheckDevice => &["C"],
+            Action::Search => &["/"],
+            Action::SearchNotes => &["F"],
+            Action::ToggleInstallable => &["f"],
+            Action::MarkDiffBase => &["b"],
+            Action::ToggleView => &["v"],
+            Action::ToggleStatusSource => &["S"],
+            Action::DownloadAll => &["D"],
+            Action::CopyInstallCommand => &["y"],
+            Action::ShowJson => &["J"],
+            Action::DeleteCached => &["x"],
+            Action::ClearCache => &["X"],
+            Action::Refresh => &["r"],
+            Action::ToggleInventory => &["i"],
+            Action::FilterLog => &["e"],
+            Action::Enqueue => &["a"],
+            Action::RunQueue => &["I"],
+            Action::WatchLogcat => &["w"],
+            Action::PauseLogcat => &["W"],
+            Action::ClearLogcat => &["c"],
+            Action::ExportReport => &["E"],
+            Action::ExpandInfo => &["Tab"],
+            Action::TogglePin => &["P"],
+            Action::JumpToTag => &[":"],
+            Action::PushOnly => &["o"],
+            Action::CycleSort => &["O"],
+            Action::ToggleActions => &["H"],
+            Action::PickAsset => &["A"],
+            Action::CopyNotes => &["Y"],
+            Action::PickDevice => &["s"],
+            Action::Quit => &["q", "Esc"],
+        }
+    }
+}
+
+const ACTIONS: [Action; 42] = [
+    Action::Next,
+    Action::Previous,
+    Action::Unselect,
+    Action::Install,
+    Action::CleanInstall,
+    Action::BroadcastInstall,
+    Action::Undo,
+    Action::Top,
+    Action::Bottom,
+    Action::SwitchUser,
+    Action::SwitchDevice,
+    Action::CheckDevice,
+    Action::Search,
+    Action::SearchNotes,
+    Action::ToggleInstallable,
+    Action::MarkDiffBase,
+    Action::ToggleView,
+    Action::ToggleStatusSource,
+    Action::DownloadAll,
+    Action::CopyInstallCommand,
+    Action::ShowJson,
+    Action::DeleteCached,
+    Action::ClearCache,
+    Action::Refresh,
+    Action::ToggleInventory,
+    Action::FilterLog,
+    Action::Enqueue,
+    Action::RunQueue,
+    Action::WatchLogcat,
+    Action::PauseLogcat,
+    Action::ClearLogcat,
+    Action::ExportReport,
+    Action::ExpandInfo,
+    Action::TogglePin,
+    Action::JumpToTag,
+    Action::PushOnly,
+    Action::CycleSort,
+    Action::ToggleActions,
+    Action::PickAsset,
+    Action::CopyNotes,
+    Action::PickDevice,
+    Action::Quit,
+];
+
+/// Keys of a single action as written in the config file, either `"j"` or `["j", "Down"]`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A key combination like `j`, `Down` or `ctrl+r`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn parse(value: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = value.split('+').collect();
+        // a plain "+" is a key on its own
+        let key = if value == "+" {
+            "+"
+        } else {
+            parts.pop().unwrap_or_default()
+        };
+
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", modifier, value)),
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("Unknown key '{}'", value)),
+                }
+            }
+        };
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(event: &KeyEvent) -> Self {
+        // the case of a character already tells whether shift was held
+        let modifiers = match event.code {
+            KeyCode::Char(_) => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Maps key combinations to actions.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, Action>,
+    keys: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the configured bindings, using the defaults for unspecified actions.
+    pub fn new(configured: HashMap<Action, Keys>) -> Result<Self, String> {
+        let mut keymap = Self {
+            bindings: HashMap::new(),
+            keys: HashMap::new(),
+        };
+        let mut conflicts = Vec::new();
+
+        for action in ACTIONS {
+            let values: Vec<String> = match configured.get(&action) {
+                Some(Keys::One(value)) => vec![value.clone()],
+                Some(Keys::Many(values)) => values.clone(),
+                None => action
+                    .default_keys()
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect(),
+            };
+
+            for value in values {
+                let binding = KeyBinding::parse(&value)?;
+                if let Some(other) = keymap.bindings.insert(binding, action) {
+                    conflicts.push(format!(
+                        "'{}' is bound to {:?} and {:?}",
+                        value, other, action
+                    ));
+                }
+                keymap.keys.entry(action).or_default().push(binding);
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(format!("Conflicting keybindings: {}", conflicts.join(", ")))
+        }
+    }
+
+    /// Returns the action bound to the pressed key.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from(event)).copied()
+    }
+
+    /// Returns the primary key of an action for the help line.
+    pub fn label(&self, action: Action) -> String {
+        self.keys
+            .get(&action)
+            .and_then(|keys| keys.first())
+            .map_or_else(String::new, |key| key.to_string())
+    }
+}
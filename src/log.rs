@@ -0,0 +1,77 @@
+use ratatui::style::Color;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// How many messages are kept, older ones are dropped.
+const CAPACITY: usize = 500;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub fn color(self) -> Color {
+        match self {
+            Severity::Info => Color::Reset,
+            Severity::Warn => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warn => write!(f, "warn"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+pub struct Entry {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Bounded list of messages shown in the log panel.
+pub struct Log {
+    entries: VecDeque<Entry>,
+    /// Messages below this severity are hidden.
+    pub min_severity: Severity,
+}
+
+impl Log {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(CAPACITY),
+            min_severity: Severity::Info,
+        }
+    }
+
+    pub fn push(&mut self, severity: Severity, message: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry { severity, message });
+    }
+
+    /// Messages at or above the minimum severity, oldest first.
+    pub fn visible(&self) -> impl DoubleEndedIterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.severity >= self.min_severity)
+    }
+
+    /// Shows all messages, then warnings and errors, then errors only.
+    pub fn cycle_filter(&mut self) {
+        self.min_severity = match self.min_severity {
+            Severity::Info => Severity::Warn,
+            Severity::Warn => Severity::Error,
+            Severity::Error => Severity::Info,
+        };
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TryRecvError, UnboundedReceiver};
+use tokio::task;
+use tokio::time::sleep;
+
+use crate::adb;
+use crate::error::AppError;
+
+/// How many lines are kept, older ones are dropped.
+const CAPACITY: usize = 1000;
+/// How long to wait for the launched app to get a process.
+const START_ATTEMPTS: usize = 10;
+const START_DELAY: Duration = Duration::from_millis(500);
+
+/// `adb logcat` of a launched app, streamed by a background task.
+pub struct Logcat {
+    pub package: String,
+    pub pid: u32,
+    pub lines: VecDeque<String>,
+    /// New lines wait in the channel while paused.
+    pub paused: bool,
+    lines_rx: UnboundedReceiver<String>,
+}
+
+impl Logcat {
+    /// Launches the app and streams the log of its process.
+    pub async fn start(serial: &str, package: &str) -> Result<Self, AppError> {
+        adb::launch(Some(serial), package).map_err(|error| AppError::Adb(error.to_string()))?;
+
+        let mut pid = None;
+        for _ in 0..START_ATTEMPTS {
+            pid = adb::pid(Some(serial), package)
+                .map_err(|error| AppError::Adb(error.to_string()))?;
+            if pid.is_some() {
+                break;
+            }
+            sleep(START_DELAY).await;
+        }
+        let pid = pid.ok_or_else(|| AppError::Adb(format!("{} is not running", package)))?;
+
+        let (lines_tx, lines_rx) = mpsc::unbounded_channel();
+        let serial = serial.to_string();
+        task::spawn_blocking(move || {
+            let filter = format!("--pid={}", pid);
+            // stops with the next line once the panel is closed and the receiver dropped
+            let result =
+                adb::shell_lines(Some(&serial), &["logcat", "-v", "time", &filter], |line| {
+                    lines_tx.send(line).is_ok()
+                });
+            if let Err(error) = result {
+                let _ = lines_tx.send(format!("logcat failed! {}", error));
+            }
+        });
+
+        Ok(Self {
+            package: package.to_string(),
+            pid,
+            lines: VecDeque::with_capacity(CAPACITY),
+            paused: false,
+            lines_rx,
+        })
+    }
+
+    /// Takes the lines received since the last call, unless paused, and returns how many.
+    /// Returns `None` once the stream ended.
+    pub fn receive(&mut self) -> Option<usize> {
+        if self.paused {
+            return Some(0);
+        }
+        let mut received = 0;
+        loop {
+            match self.lines_rx.try_recv() {
+                Ok(line) => {
+                    if self.lines.len() == CAPACITY {
+                        self.lines.pop_front();
+                    }
+                    self.lines.push_back(line);
+                    received += 1;
+                }
+                Err(TryRecvError::Empty) => return Some(received),
+                Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+}
@@ -1,36 +1,98 @@
-use adb_client::AdbTcpConnection;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::{
     event::KeyEventKind,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use ratatui::backend::Backend;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Alignment, Constraint, Layout, Rect};
 use ratatui::prelude::{Stylize, Terminal};
 use ratatui::style::palette::tailwind;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
-use ratatui::widgets::block::Title;
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::block::{Position, Title};
 use ratatui::widgets::{
-    BorderType, Clear, Gauge, ListState, Padding, Paragraph, StatefulWidget, Widget,
+    BorderType, Clear, Gauge, LineGauge, ListState, Padding, Paragraph, StatefulWidget, Widget,
+    Wrap,
 };
 use ratatui::{
     backend::CrosstermBackend,
     widgets::{Block, Borders, List, ListItem},
 };
+use similar::{ChangeTag, TextDiff};
 
-use std::fs::File;
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::io::{stdout, Result};
-use std::net::Ipv4Addr;
-use std::path::Path;
-use std::{env, io};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{io, process};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
+mod adb;
+mod apk;
+mod broadcast;
+mod browser;
+mod cache;
+mod changelog;
+mod checksums;
+mod clipboard;
+mod config;
+mod error;
 mod github;
-use github::{download_asset, fetch_releases, Release};
+mod hooks;
+mod install;
+mod keys;
+mod log;
+mod logcat;
+mod pins;
+mod report;
+mod selector;
+mod setup;
+mod sort;
+mod transfer;
+mod watch;
+use adb::{Device, Package, User};
+use apk::ApkInfo;
+use broadcast::{DeviceInstall, InstallState, InstallUpdate};
+use cache::Cache;
+use checksums::Checksums;
+use config::{path_safe, Config, Repo, ScrollMode, ViewMode};
+use error::AppError;
+use github::{
+    matching_assets, resolve_asset, Asset, Continuation, GithubClient, Reactions, Release,
+};
+use install::{install_asset, Phase, Replace};
+use keys::Action;
+use log::{Log, Severity};
+use logcat::Logcat;
+use pins::Pins;
+use report::InstallRecord;
+use selector::Selector;
+use sort::{Sort, SortKey};
+use transfer::{Throughput, Transfer, TransferState, TransferUpdate};
+use watch::NewReleases;
 
 const GAUGE_COLOR: Color = tailwind::GREEN.c800;
+const MATCH_COLOR: Color = Color::Yellow;
+/// Tags of releases published while watching.
+const NEW_COLOR: Color = Color::LightMagenta;
+const HIGHLIGHT_SYMBOL: &str = "► ";
+/// Width of the date, size and status columns of the detailed view.
+const DETAILS_WIDTH: usize = 2 + 10 + 2 + 9 + 2 + 9;
+/// Below this width the panels are stacked instead of side by side.
+const NARROW_WIDTH: u16 = 100;
+/// How many status changes can be undone.
+const MAX_HISTORY: usize = 20;
+/// How long to wait for input before handling background updates.
+const TICK_RATE: Duration = Duration::from_millis(100);
+/// How often the open device picker asks the ADB server for the devices.
+const DEVICE_REFRESH: Duration = Duration::from_secs(1);
 
 /// Indicates if a Release was installed before already.
 #[derive(Copy, Clone)]
@@ -39,292 +101,4510 @@ enum Status {
     Installed,
 }
 
-struct ReleaseItem<'a> {
-    tag_name: &'a str,
-    body: &'a str,
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Open => write!(f, "open"),
+            Status::Installed => write!(f, "installed"),
+        }
+    }
+}
+
+/// Whether the notes and assets of a release are complete.
+enum Details {
+    Complete,
+    /// Fetched on their own once the release gets selected.
+    Pending,
+    Loading,
+    Failed(String),
+}
+
+/// Where the installed status of the releases comes from.
+#[derive(Copy, Clone, PartialEq)]
+enum StatusSource {
+    /// The statuses changed by hand and by installs.
+    Recorded,
+    /// The release whose version the device reports for the last installed package.
+    Device,
+}
+
+/// What the left side of the screen lists.
+#[derive(Copy, Clone, PartialEq)]
+enum Screen {
+    Releases,
+    /// Third-party apps installed on the device.
+    Inventory,
+}
+
+/// Releases fetched beyond the limit and where they continue.
+type MoreReleases = (Vec<Release>, Option<Continuation>);
+
+/// Releases of all sources, newest first, and where those of each source continue.
+type FetchedSources = (Vec<(usize, Release)>, Vec<Option<Continuation>>);
+
+/// Message sent from a background task to the UI.
+enum TaskEvent {
+    /// Progress of the install.
+    Progress(Phase, u64, Option<u64>),
+    Message(Severity, String),
+    /// The install finished, what follows it may still be running.
+    Installed(std::result::Result<Installed, AppError>),
+    /// The screenshot, post-install commands and keeping the APK after the install are done.
+    InstallDone,
+    Pushed {
+        asset: String,
+        device: String,
+        result: std::result::Result<String, AppError>,
+    },
+    /// The install command to copy, once its APK was downloaded.
+    InstallCommand(std::result::Result<String, AppError>),
+    Refreshed(std::result::Result<FetchedSources, AppError>),
+    /// The devices listed for the device picker.
+    Devices(std::result::Result<Vec<Device>, AppError>),
+    /// The device to install to is ready, with its SDK version.
+    DeviceChecked(std::result::Result<(Device, Option<u32>), AppError>),
+    /// Several devices are ready and none was chosen, the one to install to has to be picked.
+    DeviceUnchosen,
+    /// The devices listed to switch to the next ready one.
+    NextDevice(std::result::Result<Vec<Device>, AppError>),
+    /// The port mappings moved to the device switched to, then its SDK version was read.
+    Switched {
+        device: Device,
+        /// Why port mappings could not be removed from the previous device.
+        warnings: Vec<String>,
+        sdk: std::result::Result<Option<u32>, AppError>,
+        /// The release to install once switched.
+        pending: Option<usize>,
+    },
+    /// The users present on the device.
+    Users(std::result::Result<Vec<User>, AppError>),
+    /// The apps installed on the device.
+    Packages(io::Result<Vec<Package>>),
+    /// The version name of the app to show the matching release of.
+    MatchingVersion {
+        package: String,
+        version: io::Result<Option<String>>,
+    },
+    /// The version name of the installed app, to tell which release is on the device.
+    VersionOnDevice {
+        package: String,
+        version: io::Result<Option<String>>,
+    },
+    /// The devices to install the release at `index` to, asking first unless `confirmed`.
+    BroadcastDevices {
+        index: usize,
+        confirmed: bool,
+        devices: std::result::Result<Vec<Device>, AppError>,
+    },
+}
+
+/// An APK that got installed.
+struct Installed {
+    /// The asset picked for the ABIs of the device, if one was.
+    picked: Option<String>,
+    sha256: Option<String>,
+    /// Package and SDK versions, unless the APK could not be read.
+    apk: Option<ApkInfo>,
+}
+
+/// The install running in the background, with what is reported once it finished.
+struct Installing {
+    source: usize,
+    id: i64,
+    tag_name: String,
+    /// Name of the asset installed, unless one is picked for the ABIs of the device.
+    asset: String,
+    repo: String,
+    device: Device,
+}
+
+/// A repository whose releases are listed.
+struct Source {
+    repo: Repo,
+    github: GithubClient,
+    /// Where the releases continue beyond the limit, fetched once the end of the list is reached.
+    more: Option<Continuation>,
+}
+
+struct ReleaseItem {
+    /// Index of the repository in [`App::sources`].
+    source: usize,
+    /// Id of the release, unlike the tag it identifies the release even if the tag was re-created.
+    id: i64,
+    tag_name: String,
+    body: String,
+    /// Pretty-printed JSON of the release as GitHub returned it.
+    raw: String,
+    published_at: Option<DateTime<Utc>>,
+    target_commitish: String,
     asset_id: i32,
+    asset: Option<Asset>,
+    /// `asset` was picked by hand, so it is installed instead of the one for the device's ABI.
+    asset_picked: bool,
+    assets: Vec<Asset>,
     status: Status,
+    /// Listed at the top, see [`Pins`].
+    pinned: bool,
+    /// Expected SHA-256 of the assets, loaded when the release is selected.
+    checksums: Checksums,
+    reactions: Reactions,
+    details: Details,
+    /// `minSdkVersion` and `targetSdkVersion` of the APK, known once it was installed.
+    sdk: Option<(Option<u32>, Option<u32>)>,
+}
+
+/// An action waiting for the user to confirm it.
+#[derive(PartialEq)]
+enum Confirmation {
+    /// Uninstall the differently signed app and install the release at the index.
+    Reinstall(usize),
+    /// Install the release at the index although it is old and newer ones exist.
+    Stale {
+        index: usize,
+        tag_name: String,
+        stale: Stale,
+    },
+    /// Install the release at the index to all ready devices.
+    Broadcast {
+        index: usize,
+        tag_name: String,
+        /// Name and size of the APK.
+        asset: (String, u64),
+        devices: Vec<String>,
+        stale: Option<Stale>,
+    },
+    /// Download all assets of the release at the index.
+    DownloadAll {
+        index: usize,
+        tag_name: String,
+        /// Names and sizes.
+        assets: Vec<(String, u64)>,
+    },
+    /// Install the queued releases one after another.
+    RunQueue {
+        /// Tags and the names and sizes of their APKs.
+        releases: Vec<(String, Option<(String, u64)>)>,
+        device: String,
+    },
+    /// Uninstall the app with its data and install the release at the index fresh.
+    CleanInstall {
+        index: usize,
+        tag_name: String,
+        stale: Option<Stale>,
+    },
+    /// Install the release at the index with `-d` over the newer installed version.
+    Downgrade {
+        index: usize,
+        package: String,
+        installed: u64,
+        apk: u64,
+    },
+    /// Delete the cached APK of a release.
+    DeleteCached {
+        source: usize,
+        asset_id: i32,
+        tag_name: String,
+    },
+    ClearCache,
+}
+
+/// How old a release is that got published longer ago than `stale_after`.
+#[derive(PartialEq)]
+struct Stale {
+    age: chrono::Duration,
+    /// Tag of the newest release of the repository.
+    newest: String,
+}
+
+/// The assets of a release, listed to download, copy or open one of them.
+struct AssetPicker {
+    /// The release the picker was opened for, which stays the same when the selection changes.
+    source: usize,
+    id: i64,
+    state: ListState,
+}
+
+/// Devices known to the ADB server, kept up to date while picking the one to install to.
+struct DevicePicker {
+    devices: Vec<Device>,
+    state: ListState,
+    /// When the devices were last listed.
+    refreshed: Option<Instant>,
+    /// Whether the devices are being listed in the background.
+    listing: bool,
+    /// Why the devices could not be listed the last time.
+    error: Option<String>,
+    /// The release to install once a device is picked.
+    pending: Option<usize>,
+}
+
+/// An operation GitHub refused with 401, retried once a new token is entered.
+enum Retry {
+    Refresh,
+    Install(usize),
+    CopyInstallCommand,
+    Details { source: usize, id: i64 },
+}
+
+/// A status change that can be undone.
+struct StatusChange {
+    source: usize,
+    id: i64,
+    tag_name: String,
+    previous: Status,
+}
+
+/// Search within the notes shown in the info panel.
+struct NotesSearch {
+    query: String,
+    /// The query is still being typed.
+    editing: bool,
+    /// Index of the match scrolled to.
+    current: usize,
+    /// Number of matches when the notes were last rendered.
+    count: usize,
+}
+
+/// The tag being typed to jump to its release.
+struct TagPrompt {
+    input: String,
+    /// What was typed before Tab and the index of the completion shown, while cycling.
+    completion: Option<(String, usize)>,
+}
+
+/// A release shown in the list and the positions of the tag characters matching the filter.
+struct Match {
+    index: usize,
+    positions: Vec<usize>,
 }
 
-struct StatefulList<'a> {
+struct StatefulList {
     state: ListState,
-    items: Vec<ReleaseItem<'a>>,
+    items: Vec<ReleaseItem>,
+    filter: String,
+    /// Hides releases without an APK asset.
+    installable_only: bool,
+    sort: Sort,
+    /// Release whose notes the selected release is compared against.
+    diff_base: Option<usize>,
+    visible: Vec<Match>,
     last_selected: Option<usize>,
     in_progress: Option<usize>,
 }
 
+/// Checksums of a release fetched in the background, by source and id.
+type ChecksumsUpdate = (
+    usize,
+    i64,
+    std::result::Result<HashMap<String, String>, AppError>,
+);
+
 // #[derive(Default)]
-struct App<'a> {
-    items: StatefulList<'a>,
+struct App {
+    config: Config,
+    /// The listed repositories, the one of `$GH_OWNER`/`$GH_REPO` first.
+    sources: Vec<Source>,
+    items: StatefulList,
+    /// Counts, APK size and dates of all listed releases, see [`summarize`].
+    summary: String,
+    screen: Screen,
+    packages: Vec<Package>,
+    packages_state: ListState,
+    device: Option<Device>,
+    users: Vec<User>,
+    log: Log,
+    /// Status changes whose install did not complete, most recent last.
+    history: Vec<StatusChange>,
+    searching: bool,
+    notes_search: Option<NotesSearch>,
+    tag_prompt: Option<TagPrompt>,
+    confirm: Option<Confirmation>,
+    /// Scroll offset of the raw JSON popup while it is open.
+    json_scroll: Option<u16>,
+    asset_picker: Option<AssetPicker>,
+    device_picker: Option<DevicePicker>,
+    /// How the next install replaces the installed app, set by a confirmation.
+    replace: Replace,
+    transfers: Vec<Transfer>,
+    /// SDK level of the device to install to, once checked.
+    device_sdk: Option<u32>,
+    /// Result of the last device check, shown in the status bar.
+    device_check: Option<std::result::Result<String, AppError>>,
+    /// Releases installed one after another, by source and id.
+    queue: VecDeque<(usize, i64)>,
+    /// Length of the queue when it was started, 0 while it is not running.
+    queue_total: usize,
+    /// Package of the last installed APK, launched for watching its logcat.
+    installed_package: Option<String>,
+    status_source: StatusSource,
+    /// Source and tag of the release found on the device when the statuses were last queried.
+    on_device: Option<(usize, i64)>,
+    logcat: Option<Logcat>,
+    /// Advances with every redraw to animate the busy indicator.
+    tick: usize,
+    /// Set when something shown changed, the UI is only drawn again then or while animating.
+    needs_redraw: bool,
+    /// What the install is busy with and its progress, downloading or pushing the APK.
+    download: Option<(Phase, Throughput)>,
+    /// The install running in the background, while it does the list stays usable.
+    installing: Option<Installing>,
+    /// Whether the releases are being fetched again.
+    refreshing: bool,
+    /// The newest release found by the watcher, installed once the releases are refreshed and
+    /// its assets are loaded.
+    auto_install: Option<(usize, i64, String)>,
+    tasks_tx: UnboundedSender<TaskEvent>,
+    tasks_rx: UnboundedReceiver<TaskEvent>,
+    updates_tx: UnboundedSender<TransferUpdate>,
+    updates_rx: UnboundedReceiver<TransferUpdate>,
+    /// Devices of the last broadcast install.
+    broadcast: Vec<DeviceInstall>,
+    /// Tag of the broadcast install while it is running.
+    broadcast_tag: Option<String>,
+    /// Repository and asset of the last broadcast install.
+    broadcast_release: Option<(String, String)>,
+    broadcast_tx: UnboundedSender<InstallUpdate>,
+    broadcast_rx: UnboundedReceiver<InstallUpdate>,
+    /// Releases fetched on their own, by source and tag name.
+    details_tx: UnboundedSender<(usize, i64, std::result::Result<Release, AppError>)>,
+    details_rx: UnboundedReceiver<(usize, i64, std::result::Result<Release, AppError>)>,
+    /// Releases fetched beyond the limit, by source.
+    more_tx: UnboundedSender<(usize, std::result::Result<MoreReleases, AppError>)>,
+    more_rx: UnboundedReceiver<(usize, std::result::Result<MoreReleases, AppError>)>,
+    /// Sources whose following releases are being fetched.
+    loading_more: usize,
+    checksums_tx: UnboundedSender<ChecksumsUpdate>,
+    checksums_rx: UnboundedReceiver<ChecksumsUpdate>,
+    /// The new token being entered after GitHub rejected the old one, and what to retry with it.
+    token_prompt: Option<(String, Retry)>,
+    /// Whether the info panel gets most of the height when the panels are stacked.
+    info_expanded: bool,
+    /// Whether the bar listing the keys is hidden, giving its rows to the panels.
+    actions_hidden: bool,
+    /// Messages of the GitHub clients, e.g. while waiting out a rate limit.
+    notices_rx: UnboundedReceiver<String>,
+    pins: Pins,
+    /// Installs performed during the session, oldest first.
+    installs: Vec<InstallRecord>,
+    /// Releases found by `--watch` since the start, by source and id.
+    new_releases: HashSet<(usize, i64)>,
+    watch_tx: UnboundedSender<std::result::Result<NewReleases, AppError>>,
+    watch_rx: UnboundedReceiver<std::result::Result<NewReleases, AppError>>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut config = Config::load().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    if config.insecure {
+        eprintln!("Warning: --insecure accepts any TLS certificate, GitHub is not verified");
+    }
+
+    if let Some(path) = &config.local_apk {
+        if let Err(error) = install_local(&config, path).await {
+            fail(error);
+        }
+        return Ok(());
+    }
+
+    if config.prune_cache {
+        if let Err(error) = prune_cache(&config) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(range) = &config.changelog {
+        if let Err(error) = print_changelog(&config, range).await {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(tag) = &config.install_tag {
+        if let Err(error) = install_release(&config, tag).await {
+            fail(error);
+        }
+        return Ok(());
+    }
+
     // Set up the terminal
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout());
-    let terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(backend)?;
+
+    if config.token.is_none() {
+        match setup::run(&mut terminal, &config).await? {
+            setup::Outcome::Token(token) => config.token = Some(token),
+            setup::Outcome::Quit => {
+                io::stdout().execute(LeaveAlternateScreen)?;
+                disable_raw_mode()?;
+                return Ok(());
+            }
+        }
+    }
 
     // Fetch GitHub releases
-    let token = match env::var_os("GH_ACCESS_TOKEN") {
-        Some(v) => v.into_string().unwrap(),
-        None => panic!("$GH_ACCESS_TOKEN is not set"),
-    };
-    let owner = env::var_os("GH_OWNER").unwrap().into_string().unwrap();
-    let repo = env::var_os("GH_REPO").unwrap().into_string().unwrap();
+    let (notices_tx, mut notices_rx) = mpsc::unbounded_channel();
+    let mut sources: Vec<Source> = config
+        .repos()
+        .into_iter()
+        .map(|repo| {
+            let mut github = GithubClient::new(&config.for_repo(&repo));
+            github.set_notices(notices_tx.clone());
+            Source {
+                github,
+                repo,
+                more: None,
+            }
+        })
+        .collect();
+    let mut notices = Vec::new();
+    let releases = {
+        let (page_tx, mut page_rx) = mpsc::unbounded_channel();
+        let githubs: Vec<GithubClient> =
+            sources.iter().map(|source| source.github.clone()).collect();
+        let fetch = fetch_sources(&githubs, config.lazy, page_tx);
+        tokio::pin!(fetch);
 
-    let releases = fetch_releases(&owner, &repo, &token)
-        .await
-        .expect("Could not fetch releases");
+        // redraw on every tick, so a stalled request still shows the app is alive
+        let mut ticker = tokio::time::interval(TICK_RATE);
+        let mut progress = vec![(1, None); sources.len()];
+        let mut tick = 0;
+        loop {
+            tokio::select! {
+                result = &mut fetch => break Some(result),
+                // raw mode turns Ctrl-C into a key, this catches SIGINT sent otherwise
+                _ = tokio::signal::ctrl_c() => break None,
+                _ = ticker.tick() => {
+                    if ctrl_c_pressed()? {
+                        break None;
+                    }
+                    while let Ok((source, page, pages)) = page_rx.try_recv() {
+                        progress[source] = (page, pages);
+                    }
+                    let page = progress.iter().map(|(page, _)| page).sum();
+                    let pages = progress.iter().map(|(_, pages)| *pages).sum();
+                    while let Ok(notice) = notices_rx.try_recv() {
+                        notices.push(notice);
+                    }
+                    tick += 1;
+                    draw_loading(&mut terminal, page, pages, notices.last(), tick)?;
+                }
+            }
+        }
+    };
+    let Some(releases) = releases else {
+        io::stdout().execute(LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+        eprintln!("Cancelled fetching the releases");
+        process::exit(130);
+    };
+    let (releases, more) = match releases {
+        Ok(releases) => releases,
+        Err(error) => {
+            io::stdout().execute(LeaveAlternateScreen)?;
+            disable_raw_mode()?;
+            eprintln!("Could not fetch releases! {}", error);
+            process::exit(error.exit_code());
+        }
+    };
+    for (source, more) in sources.iter_mut().zip(more) {
+        source.more = more;
+    }
 
-    App::new(&releases).run(terminal).await?;
+    let mut app = App::new(config, sources, &releases, notices_rx);
+    for notice in notices {
+        app.warn(notice);
+    }
+    app.run(terminal).await?;
 
     io::stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
+
+    if let Some(path) = &app.config.report {
+        if !app.installs.is_empty() {
+            if let Err(error) = report::write(path, &app.installs) {
+                eprintln!(
+                    "Could not write the report to {}! {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+    if let Some(device) = &app.device {
+        for warning in install::close_port_mappings(&app.config, &device.serial) {
+            eprintln!("{}", warning);
+        }
+    }
+    Ok(())
+}
+
+/// Whether Ctrl-C was pressed since the last call, other keys are dropped.
+fn ctrl_c_pressed() -> Result<bool> {
+    while event::poll(Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press
+                && key.code == KeyCode::Char('c')
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Shows which page of releases is being fetched.
+fn draw_loading(
+    terminal: &mut Terminal<impl Backend>,
+    page: usize,
+    pages: Option<usize>,
+    notice: Option<&String>,
+    tick: usize,
+) -> Result<()> {
+    let mut text = match pages {
+        Some(pages) => format!(
+            "{} Fetching releases… page {} of {}",
+            spinner(tick),
+            page,
+            pages
+        ),
+        None => format!("{} Fetching releases… page {}", spinner(tick), page),
+    };
+    if let Some(notice) = notice {
+        text += &format!("\n{}", notice);
+    }
+    text += "\nCtrl-C to cancel";
+    terminal.draw(|f| {
+        let area = centered_rect(f.size(), 60, 20);
+        f.render_widget(
+            Paragraph::new(text)
+                .block(Block::bordered().border_type(BorderType::Rounded))
+                .centered(),
+            area,
+        )
+    })?;
+    Ok(())
+}
+
+/// Takes a screenshot, runs the post-install commands and keeps the APK as configured once it
+/// got installed, reporting over `events`. The commands need the package of the APK.
+async fn after_install(
+    config: &Config,
+    path: &Path,
+    package: Option<&str>,
+    tag: &str,
+    asset: &str,
+    serial: &str,
+    events: &UnboundedSender<TaskEvent>,
+) {
+    let report = |severity, message| {
+        let _ = events.send(TaskEvent::Message(severity, message));
+    };
+    if let Some(dir) = &config.screenshot_dir {
+        match install::capture_screenshot(config, serial, path, dir).await {
+            Ok(path) => report(
+                Severity::Info,
+                format!("Screenshot saved to {}", path.display()),
+            ),
+            Err(error) => report(
+                Severity::Warn,
+                format!("Could not take a screenshot! {}", error),
+            ),
+        }
+    }
+    if let (false, Some(package)) = (config.post_install.is_empty(), package) {
+        let values = hooks::Values {
+            package,
+            tag,
+            asset,
+            serial,
+        };
+        match hooks::run_all(&config.post_install, &values).await {
+            Ok(()) => report(
+                Severity::Info,
+                format!("Ran {} post-install command(s)", config.post_install.len()),
+            ),
+            Err(error) => report(Severity::Error, AppError::PostInstall(error).to_string()),
+        }
+    }
+    match install::keep_apk(config, path, tag).await {
+        Ok(Some(path)) => report(
+            Severity::Info,
+            format!("Kept the APK as {}", path.display()),
+        ),
+        Ok(None) => {}
+        Err(error) => report(Severity::Warn, format!("Could not keep the APK! {}", error)),
+    }
+}
+
+/// Fetches the releases of all sources concurrently, newest first, along with where the releases
+/// of each source continue beyond the limit. `on_page` receives the index of the source along
+/// with the page numbers.
+async fn fetch_sources(
+    sources: &[GithubClient],
+    lazy: bool,
+    on_page: UnboundedSender<(usize, usize, Option<usize>)>,
+) -> std::result::Result<FetchedSources, AppError> {
+    let fetches: Vec<_> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, github)| {
+            let github = github.clone();
+            let on_page = on_page.clone();
+            tokio::spawn(async move {
+                let on_page = |page, pages| {
+                    let _ = on_page.send((index, page, pages));
+                };
+                github.fetch_from(github.first_page(lazy), on_page).await
+            })
+        })
+        .collect();
+
+    let mut releases = Vec::new();
+    let mut more = Vec::new();
+    for (index, fetch) in fetches.into_iter().enumerate() {
+        let (fetched, continuation) = fetch
+            .await
+            .map_err(|error| AppError::Github(error.to_string()))??;
+        releases.extend(fetched.into_iter().map(|release| (index, release)));
+        more.push(continuation);
+    }
+    if sources.len() > 1 {
+        releases.sort_by_key(|(_, release)| Reverse(release.published_at));
+    }
+    Ok((releases, more))
+}
+
+/// Prints the notes of the releases in the range as Markdown without starting the TUI.
+async fn print_changelog(
+    config: &Config,
+    range: &changelog::Range,
+) -> std::result::Result<(), String> {
+    let releases = GithubClient::new(config)
+        .fetch_releases(|_, _| {})
+        .await
+        .map_err(|error| error.to_string())?;
+    print!("{}", changelog::render(&releases, range)?);
+    Ok(())
+}
+
+/// The longest prefix all tags start with.
+fn common_prefix<'a>(tags: &[&'a str]) -> &'a str {
+    let first = tags[0];
+    let len = tags[1..].iter().fold(first.len(), |len, tag| {
+        first[..len]
+            .char_indices()
+            .zip(tag.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(tag.len()), |((i, _), _)| i)
+    });
+    &first[..len]
+}
+
+/// One line like ` 120 releases · 98 installable · 3.2 GB of APKs · 2021-03-01 – 2024-06-10 `.
+///
+/// Releases whose assets are not loaded yet count as neither installable nor not.
+fn summarize(items: &[ReleaseItem]) -> String {
+    let loaded: Vec<&ReleaseItem> = items
+        .iter()
+        .filter(|item| matches!(item.details, Details::Complete))
+        .collect();
+    let installable = loaded.iter().filter(|item| item.asset.is_some()).count();
+    let apk_size: u64 = loaded
+        .iter()
+        .flat_map(|item| &item.assets)
+        .filter(|asset| asset.name.ends_with(".apk"))
+        .map(|asset| asset.size)
+        .sum();
+
+    let mut summary = format!(" {} releases · {} installable", items.len(), installable);
+    if loaded.len() < items.len() {
+        summary += &format!(" ({} not loaded)", items.len() - loaded.len());
+    }
+    summary += &format!(" · {} of APKs", format_size(apk_size));
+    let dates = items.iter().filter_map(|item| item.published_at);
+    if let (Some(oldest), Some(newest)) = (dates.clone().min(), dates.max()) {
+        summary += &format!(
+            " · {} – {}",
+            oldest.format("%Y-%m-%d"),
+            newest.format("%Y-%m-%d")
+        );
+    }
+    summary + " "
+}
+
+/// Whether several devices are ready, so the one to install to can not just be the first.
+fn several_ready() -> bool {
+    adb::devices().is_ok_and(|devices| {
+        devices
+            .iter()
+            .filter(|device| device.state == "device")
+            .count()
+            > 1
+    })
+}
+
+/// Identifies the repository in [`Pins`], also by host as ids are per GitHub instance.
+fn pin_key(config: &Config, repo: &Repo) -> String {
+    format!("{}/{}", config.host, repo)
+}
+
+/// Installs an asset of the release with the tag without starting the TUI.
+async fn install_release(config: &Config, input: &str) -> std::result::Result<(), AppError> {
+    let github = GithubClient::new(config);
+    let releases = github.fetch_releases(|_, _| {}).await?;
+    let selector = Selector::parse(input);
+    // the newest one if the tag was re-created or several match
+    let release = releases
+        .iter()
+        .find(|release| selector.matches(&release.tag_name, &release.target_commitish))
+        .ok_or_else(|| AppError::NoAsset(format!("No release {}", selector)))?;
+    let tag = release.tag_name.as_str();
+
+    let assets = matching_assets(release, &config.asset_patterns);
+    let asset = match &config.asset {
+        Some(wanted) => assets
+            .iter()
+            .find(|asset| &asset.name == wanted || asset.label.as_ref() == Some(wanted))
+            .or_else(|| wanted.parse::<usize>().ok().and_then(|i| assets.get(i)))
+            .ok_or_else(|| {
+                let names: Vec<String> = assets
+                    .iter()
+                    .map(|asset| match &asset.label {
+                        Some(label) if !label.is_empty() => format!("{} ({})", asset.name, label),
+                        _ => asset.name.clone(),
+                    })
+                    .collect();
+                AppError::NoAsset(format!(
+                    "No asset '{}' in {}, installable are: {}",
+                    wanted,
+                    tag,
+                    names.join(", ")
+                ))
+            })?,
+        None => assets
+            .first()
+            .ok_or_else(|| AppError::NoAsset(format!("No APK asset found in {}", tag)))?,
+    };
+
+    let checksums = checksums::load(&github, &release.assets).await?;
+    let device = install::open_session(config)?;
+    let result = install_asset(
+        &github,
+        config,
+        &device.serial,
+        asset,
+        checksums.get(&asset.name).map(String::as_str),
+        Replace {
+            allow_downgrade: config.allow_downgrade,
+            ..Replace::default()
+        },
+        |_, _, _| {},
+        |message| eprintln!("{}", message),
+    )
+    .await;
+    for warning in install::close_port_mappings(config, &device.serial) {
+        eprintln!("{}", warning);
+    }
+    let path = result?;
+    println!(
+        "Installed {} ({}) to {} for user {}",
+        tag, asset.name, device, config.user_id
+    );
+    let package = if config.post_install.is_empty() {
+        None
+    } else {
+        Some(apk::inspect(&path)?.package)
+    };
+    match install::keep_apk(config, &path, tag).await {
+        Ok(Some(path)) => println!("Kept the APK as {}", path.display()),
+        Ok(None) => {}
+        Err(error) => eprintln!("Could not keep the APK! {}", error),
+    }
+    if let Some(package) = package {
+        let values = hooks::Values {
+            package: &package,
+            tag,
+            asset: &asset.name,
+            serial: &device.serial,
+        };
+        hooks::run_all(&config.post_install, &values)
+            .await
+            .map_err(AppError::PostInstall)?;
+    }
+    println!(
+        "status=ok code=0 tag={:?} asset={:?} serial={:?} user={}",
+        tag, asset.name, device.serial, config.user_id
+    );
+    Ok(())
+}
+
+/// Prunes the cache of the repository to the configured limits.
+fn prune_cache(config: &Config) -> std::result::Result<(), String> {
+    if !config.cache_limits.is_set() {
+        return Err("--prune-cache requires cache_max_age or cache_max_size".to_string());
+    }
+    let freed = Cache::new(config)
+        .prune(&config.cache_limits)
+        .map_err(|error| format!("Could not prune the cache! {}", error))?;
+    println!("Pruned the cache, freed {}", format_size(freed));
+    Ok(())
+}
+
+/// Installs an APK from disk without contacting GitHub or starting the TUI.
+async fn install_local(config: &Config, path: &Path) -> std::result::Result<(), AppError> {
+    let device = install::open_session(config)?;
+    let result = install::install_apk(
+        config,
+        &device.serial,
+        path,
+        Replace {
+            allow_downgrade: config.allow_downgrade,
+            ..Replace::default()
+        },
+        |_, _, _| {},
+        |message| eprintln!("{}", message),
+    )
+    .await;
+    for warning in install::close_port_mappings(config, &device.serial) {
+        eprintln!("{}", warning);
+    }
+    result?;
+    println!(
+        "Installed {} to {} for user {}",
+        path.display(),
+        device,
+        config.user_id
+    );
+    println!(
+        "status=ok code=0 apk={:?} serial={:?} user={}",
+        path.display().to_string(),
+        device.serial,
+        config.user_id
+    );
     Ok(())
 }
 
-impl Widget for &mut App<'_> {
+/// Reports a failed install with a final `status=error code=<n> error="..."` line for scripts and
+/// exits with the code of the error.
+fn fail(error: AppError) -> ! {
+    let code = error.exit_code();
+    eprintln!("{}", error);
+    println!("status=error code={} error={:?}", code, error.to_string());
+    process::exit(code);
+}
+
+impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let outer_layout = Layout::vertical([Constraint::Percentage(90), Constraint::Fill(2)]);
+        let actions_height = if self.actions_hidden {
+            Constraint::Length(0)
+        } else {
+            Constraint::Fill(2)
+        };
+        let outer_layout = Layout::vertical([Constraint::Percentage(90), actions_height]);
         let [top_area, actions_area] = outer_layout.areas(area);
 
-        let inner_layout =
-            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]);
-        let [releases_area, info_area] = inner_layout.areas(top_area);
+        let inner_layout = if area.width < NARROW_WIDTH {
+            let list_height = if self.info_expanded {
+                Constraint::Percentage(25)
+            } else {
+                Constraint::Percentage(50)
+            };
+            Layout::vertical([list_height, Constraint::Min(0)])
+        } else {
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+        };
+        let [releases_area, right_area] = inner_layout.areas(top_area);
+
+        let transfers_height = if self.transfers.is_empty() {
+            0
+        } else {
+            self.transfers.len() as u16 + 2
+        };
+        let broadcast_height = if self.broadcast.is_empty() {
+            0
+        } else {
+            self.broadcast.len() as u16 + 2
+        };
+        let logcat_height = if self.logcat.is_some() {
+            Constraint::Percentage(40)
+        } else {
+            Constraint::Length(0)
+        };
+        let right_layout = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Max(transfers_height),
+            Constraint::Max(broadcast_height),
+            logcat_height,
+            Constraint::Length(8),
+        ]);
+        let [info_area, transfers_area, broadcast_area, logcat_area, log_area] =
+            right_layout.areas(right_area);
 
-        self.render_releases(releases_area, buf);
+        match self.screen {
+            Screen::Releases => self.render_releases(releases_area, buf),
+            Screen::Inventory => self.render_inventory(releases_area, buf),
+        }
         self.render_info(info_area, buf);
-        self.render_actions(actions_area, buf);
+        if !self.transfers.is_empty() {
+            self.render_transfers(transfers_area, buf);
+        }
+        if !self.broadcast.is_empty() {
+            self.render_broadcast(broadcast_area, buf);
+        }
+        if let Some(logcat) = &self.logcat {
+            render_logcat(logcat, logcat_area, buf);
+        }
+        self.render_log(log_area, buf);
+        if !self.actions_hidden {
+            self.render_actions(actions_area, buf);
+        }
 
         if self.items.in_progress.is_some() {
             self.render_popup(top_area, buf);
         }
+        if let Some(scroll) = self.json_scroll {
+            self.render_json(scroll, top_area, buf);
+        }
+        if self.asset_picker.is_some() {
+            self.render_asset_picker(top_area, buf);
+        }
+        if self.device_picker.is_some() {
+            self.render_device_picker(top_area, buf);
+        }
+        if let Some(confirmation) = &self.confirm {
+            render_confirm(confirmation, top_area, buf);
+        }
+        if let Some((input, _)) = &self.token_prompt {
+            render_token_prompt(input, top_area, buf);
+        }
     }
 }
 
-impl App<'_> {
+impl App {
     fn render_releases(&mut self, area: Rect, buf: &mut Buffer) {
+        // with several repositories each row starts with the name of its repository
+        let repo_width = if self.sources.len() > 1 {
+            self.sources
+                .iter()
+                .map(|source| source.repo.repo.chars().count() + 1)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let any_pinned = self.items.items.iter().any(|item| item.pinned);
+        let pin_width = if any_pinned { 2 } else { 0 };
+        // room inside the borders next to the highlight symbol, the status glyph and the star
+        let available = (area.width.saturating_sub(2) as usize)
+            .saturating_sub(HIGHLIGHT_SYMBOL.chars().count() + 2 + pin_width + repo_width);
+        let detailed = self.config.view == ViewMode::Detailed && available > DETAILS_WIDTH;
+        let max_tag_width = if detailed {
+            available - DETAILS_WIDTH
+        } else {
+            available
+        };
+        let tag_width = self
+            .items
+            .items
+            .iter()
+            .map(|r| r.tag_name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(max_tag_width);
+
         // Convert releases to ListItems
         let items: Vec<ListItem> = self
             .items
-            .items
+            .visible
             .iter()
-            .map(|r| ListItem::new(r.tag_name.to_string()))
+            .map(|m| {
+                let item = &self.items.items[m.index];
+                let tag_length = item.tag_name.chars().count();
+                let truncated = tag_length > tag_width;
+                let shown = if truncated {
+                    tag_width.saturating_sub(1)
+                } else {
+                    tag_length
+                };
+                let mut spans = vec![match self.queue_position(item) {
+                    Some(position) if position < 10 => Span::styled(
+                        format!("{} ", position),
+                        Style::default().fg(Color::LightBlue),
+                    ),
+                    Some(_) => Span::styled("+ ", Style::default().fg(Color::LightBlue)),
+                    None if self.items.in_progress == Some(m.index) => Span::styled(
+                        format!("{} ", spinner(self.tick)),
+                        Style::default().fg(Color::LightGreen),
+                    ),
+                    None => item.status_glyph(self.tick, self.shown_status(item)),
+                }];
+                if any_pinned {
+                    spans.push(if item.pinned {
+                        Span::styled("★ ", Style::default().fg(Color::Yellow))
+                    } else {
+                        Span::raw("  ")
+                    });
+                }
+                if repo_width > 0 {
+                    spans.push(Span::styled(
+                        format!(
+                            "{:width$}",
+                            self.sources[item.source].repo.repo,
+                            width = repo_width
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                let tag_style = if self.new_releases.contains(&(item.source, item.id)) {
+                    Style::default().fg(NEW_COLOR).bold()
+                } else {
+                    Style::default()
+                };
+                spans.extend(item.tag_name.chars().take(shown).enumerate().map(|(i, c)| {
+                    if m.positions.contains(&i) {
+                        Span::styled(c.to_string(), Style::default().fg(MATCH_COLOR).bold())
+                    } else {
+                        Span::styled(c.to_string(), tag_style)
+                    }
+                }));
+                if truncated && tag_width > 0 {
+                    spans.push(Span::raw("…"));
+                }
+
+                if detailed {
+                    let padding = tag_width.saturating_sub(tag_length);
+                    let date = item
+                        .published_at
+                        .map_or_else(|| "-".to_string(), |d| d.format("%Y-%m-%d").to_string());
+                    let size = item
+                        .asset
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), |a| format_size(a.size));
+                    spans.push(Span::styled(
+                        format!(
+                            "{}  {}  {:>9}  {}",
+                            " ".repeat(padding),
+                            date,
+                            size,
+                            self.shown_status(item)
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                if self.items.in_progress == Some(m.index) {
+                    spans.push(Span::styled(
+                        format!(" {}", self.row_progress()),
+                        Style::default().fg(Color::LightGreen),
+                    ));
+                }
+
+                ListItem::new(Line::from(spans))
+            })
             .collect();
 
+        let mut title = match self.status_source {
+            StatusSource::Recorded => "GitHub Releases · recorded".to_string(),
+            StatusSource::Device => "GitHub Releases · on device".to_string(),
+        };
+        if self.searching || !self.items.filter.is_empty() {
+            title += &format!(" /{}", self.items.filter);
+        }
+        if let Some(prompt) = &self.tag_prompt {
+            title += &format!(" :{}", prompt.input);
+        }
+        if self.items.installable_only {
+            title += " (installable only)";
+        }
+        if self.items.sort != Sort::default() {
+            title += &format!(" · {}", self.items.sort);
+        }
+        if self.loading_more > 0 {
+            title += " · loading more";
+        } else if self.sources.iter().any(|source| source.more.is_some()) {
+            title += " · more below";
+        }
+        if self.config.watch {
+            title += " · watching";
+        }
+
         // releases
-        let list = List::new(items.clone())
+        let summary = Title::from(self.summary.as_str())
+            .position(Position::Bottom)
+            .alignment(Alignment::Right);
+        let list = List::new(items)
             .block(
                 Block::default()
-                    .title("GitHub Releases")
+                    .title(title)
+                    .title(summary)
                     .borders(Borders::ALL),
             )
             .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
-            .highlight_symbol("► ");
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+        if self.config.scroll_mode == ScrollMode::Center {
+            if let Some(selected) = self.items.state.selected() {
+                let height = area.height.saturating_sub(2) as usize;
+                let max_offset = self.items.visible.len().saturating_sub(height);
+                *self.items.state.offset_mut() =
+                    selected.saturating_sub(height / 2).min(max_offset);
+            }
+        }
 
         StatefulWidget::render(list, area, buf, &mut self.items.state);
     }
 
-    fn render_info(&mut self, area: Rect, buf: &mut Buffer) {
-        let info = if let Some(i) = self.items.state.selected() {
-            self.items.items[i].body.to_string()
-        } else {
-            "Select a release on the left side to see its description here...".to_string()
+    fn render_inventory(&mut self, area: Rect, buf: &mut Buffer) {
+        let items: Vec<ListItem> = self
+            .packages
+            .iter()
+            .map(|package| {
+                let version = package
+                    .version_code
+                    .map_or_else(|| "-".to_string(), |code| code.to_string());
+                ListItem::new(Line::from(vec![
+                    Span::raw(package.name.as_str()),
+                    Span::styled(
+                        format!("  {}", version),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let title = match &self.device {
+            Some(device) => format!("Installed on {}", device),
+            None => "Installed apps".to_string(),
         };
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
 
-        Paragraph::new(info)
-            .block(Block::new().borders(Borders::ALL))
-            .bold()
-            .render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut self.packages_state);
     }
 
-    fn render_popup(&mut self, area: Rect, buf: &mut Buffer) {
-        let popup_layout = Layout::vertical([
-            Constraint::Percentage((100 - 20) / 2),
-            Constraint::Percentage(20),
-            Constraint::Percentage((100 - 20) / 2),
-        ])
-        .split(area);
-
-        let popup_area = Layout::horizontal([
-            Constraint::Percentage((100 - 60) / 2),
-            Constraint::Percentage(60),
-            Constraint::Percentage((100 - 60) / 2),
-        ])
-        .split(popup_layout[1])[1];
+    fn render_info(&mut self, area: Rect, buf: &mut Buffer) {
+        let mut title = String::new();
+        let mut scroll = 0;
+        let mut wrapped = false;
+        let info = match (self.items.diff_base, self.items.selected()) {
+            (Some(base), Some(i)) if base != i => {
+                let base = &self.items.items[base];
+                let target = &self.items.items[i];
+                title = format!("Changes {} → {}", base.tag_name, target.tag_name);
+                Text::from(diff_lines(&base.body, &target.body))
+            }
+            (_, Some(i)) => {
+                let item = &self.items.items[i];
+                match &item.details {
+                    Details::Loading => {
+                        title = format!("{} Loading {}", spinner(self.tick), item.tag_name)
+                    }
+                    Details::Failed(error) => {
+                        title = format!("Could not load {}! {}", item.tag_name, error)
+                    }
+                    Details::Complete | Details::Pending => {}
+                }
+                match &mut self.notes_search {
+                    Some(search) if !search.query.is_empty() => {
+                        // wrapped here instead of by the paragraph to know the row of each match
+                        let width = area.width.saturating_sub(2) as usize;
+                        let (lines, rows) =
+                            highlight_matches(&item.body, &search.query, width, search.current);
+                        search.count = rows.len();
+                        search.current = search.current.min(rows.len().saturating_sub(1));
+                        scroll = rows
+                            .get(search.current)
+                            .map_or(0, |row| row.saturating_sub(2));
+                        wrapped = true;
+                        Text::from(lines)
+                    }
+                    _ => Text::from(item.body.to_string()),
+                }
+            }
+            _ => Text::from(
+                "Select a release on the left side to see its description here...".to_string(),
+            ),
+        };
 
-        Clear.render(popup_area, buf);
-        let title = Title::from("Progress").alignment(Alignment::Center);
-        let title = Block::new()
-            .borders(Borders::NONE)
-            .padding(Padding::vertical(1))
-            .title(title);
+        let selected = self.items.selected().map(|i| &self.items.items[i]);
+        let asset = selected.and_then(|item| item.asset.as_ref().map(|asset| (item, asset)));
+        let details = asset.map(|(item, asset)| {
+            let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+            let date_format = "%Y-%m-%d %H:%M UTC";
+            let mut details = Vec::new();
+            if asset.display_name() != asset.name {
+                details.push(Line::from(vec![
+                    label("File:     "),
+                    asset.name.clone().into(),
+                ]));
+            }
+            details.extend([
+                Line::from(vec![label("Type:     "), asset.content_type.clone().into()]),
+                Line::from(vec![
+                    label("Uploaded: "),
+                    asset.created_at.format(date_format).to_string().into(),
+                ]),
+                Line::from(vec![
+                    label("Updated:  "),
+                    asset.updated_at.format(date_format).to_string().into(),
+                ]),
+            ]);
+            if item.sdk.is_some() || self.device_sdk.is_some() {
+                details.push(sdk_line(item.sdk, self.device_sdk));
+            }
+            if checksums::manifest(&item.assets).is_some() {
+                let checksum = match &item.checksums {
+                    Checksums::Pending | Checksums::Loading => Span::raw("loading…"),
+                    Checksums::Loaded(_) => match item.checksums.get(&asset.name) {
+                        Some(hash) => Span::raw(hash.to_string()),
+                        None => Span::styled("not listed", Style::default().fg(Color::Yellow)),
+                    },
+                    Checksums::Failed(error) => {
+                        Span::styled(error.clone(), Style::default().fg(Color::Red))
+                    }
+                };
+                details.push(Line::from(vec![label("SHA-256:  "), checksum]));
+            }
+            (asset.display_name(), details)
+        });
 
-        // TODO: get a real progress?
-        Gauge::default()
-            .block(title)
-            .gauge_style(GAUGE_COLOR)
-            .percent(100u16)
-            .render(popup_area, buf);
-        Block::bordered()
-            .borders(Borders::NONE)
-            .title("Progress")
-            .render(popup_area, buf);
-    }
+        let [body_area, asset_area] = match &details {
+            Some((_, lines)) => {
+                let height = lines.len() as u16 + 2;
+                Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(area)
+            }
+            None => [area, Rect::default()],
+        };
 
-    fn render_actions(&mut self, area: Rect, buf: &mut Buffer) {
-        // actions
-        let actions: Line = vec![
-            Span::styled("↓↑".to_string(), Style::default().fg(Color::LightBlue)),
-            " to move ".into(),
-            Span::styled("←".to_string(), Style::default().fg(Color::LightBlue)),
-            " to unselect ".into(),
-            Span::styled("→".to_string(), Style::default().fg(Color::LightBlue)),
-            " to change status ".into(),
-            Span::styled("g/G".to_string(), Style::default().fg(Color::LightBlue)),
-            " to go to top/bottom ".into(),
-            Span::styled("q".to_string(), Style::default().fg(Color::LightBlue)),
-            " to quit ".into(),
-        ]
-        .into();
+        if let Some(search) = &self.notes_search {
+            title += &format!(" /{}", search.query);
+            if !search.editing || !search.query.is_empty() {
+                title += &match search.count {
+                    0 => " (no matches)".to_string(),
+                    count => format!(" ({}/{})", search.current + 1, count),
+                };
+            }
+        }
 
-        Paragraph::new(actions)
+        // of the release whose notes are shown, not while comparing two
+        let reactions = match (self.items.diff_base, self.items.selected()) {
+            (Some(base), Some(i)) if base != i => String::new(),
+            (_, Some(i)) => self.items.items[i]
+                .reactions
+                .counts()
+                .iter()
+                .map(|(emoji, count)| format!(" {} {} ", emoji, count))
+                .collect(),
+            (_, None) => String::new(),
+        };
+        let reactions = Title::from(reactions)
+            .position(Position::Bottom)
+            .alignment(Alignment::Right);
+        let mut paragraph = Paragraph::new(info)
             .block(
                 Block::new()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded),
+                    .title(title)
+                    .title(reactions)
+                    .borders(Borders::ALL),
             )
-            .centered()
-            .render(area, buf);
-    }
+            .scroll((scroll as u16, 0))
+            .bold();
+        if !wrapped {
+            paragraph = paragraph.wrap(Wrap { trim: false });
+        }
+        paragraph.render(body_area, buf);
+
+        if let Some((name, details)) = details {
+            Paragraph::new(details)
+                .block(Block::new().title(name).borders(Borders::ALL))
+                .render(asset_area, buf);
+        }
+    }
+
+    fn render_transfers(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new().title("Downloads").borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        for (transfer, row) in self.transfers.iter().zip(inner.rows()) {
+            let [name_area, gauge_area, state_area] = Layout::horizontal([
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ])
+            .spacing(1)
+            .areas(row);
+
+            let state_color = match transfer.state {
+                TransferState::Done => Color::Green,
+                TransferState::Failed(_) => Color::Red,
+                _ => Color::Reset,
+            };
+
+            Paragraph::new(transfer.name.as_str()).render(name_area, buf);
+            LineGauge::default()
+                .gauge_style(GAUGE_COLOR)
+                .ratio(transfer.state.ratio())
+                .render(gauge_area, buf);
+            Paragraph::new(transfer.state.label())
+                .style(Style::default().fg(state_color))
+                .render(state_area, buf);
+        }
+    }
+
+    fn render_broadcast(&mut self, area: Rect, buf: &mut Buffer) {
+        let (installed, failed) = self.broadcast_counts();
+        let mut title = format!("Devices · {}/{} installed", installed, self.broadcast.len());
+        if failed > 0 {
+            title += &format!(", {} failed", failed);
+        }
+        if self.broadcast_tag.is_some() {
+            title = format!("{} {}", spinner(self.tick), title);
+        }
+        let block = Block::new().title(title).borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        for (install, row) in self.broadcast.iter().zip(inner.rows()) {
+            let [device_area, state_area] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .spacing(1)
+                    .areas(row);
+            let state_color = match install.state {
+                InstallState::Installed => Color::Green,
+                InstallState::Failed(_) => Color::Red,
+                _ => Color::Reset,
+            };
+
+            Paragraph::new(install.device.to_string()).render(device_area, buf);
+            Paragraph::new(install.state.label())
+                .style(Style::default().fg(state_color))
+                .render(state_area, buf);
+        }
+    }
+
+    fn render_log(&mut self, area: Rect, buf: &mut Buffer) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let mut lines: Vec<Line> = self
+            .log
+            .visible()
+            .rev()
+            .take(visible)
+            .map(|entry| {
+                Line::styled(
+                    entry.message.as_str(),
+                    Style::default().fg(entry.severity.color()),
+                )
+            })
+            .collect();
+        lines.reverse();
+
+        let mut title = format!("Log (user {})", self.config.user_id);
+        if self.log.min_severity > Severity::Info {
+            title += &format!(" [{}+]", self.log.min_severity);
+        }
+        Paragraph::new(lines)
+            .block(Block::new().title(title).borders(Borders::ALL))
+            .render(area, buf);
+    }
+
+    fn render_popup(&mut self, area: Rect, buf: &mut Buffer) {
+        let popup_area = centered_rect(area, 60, 20);
+
+        Clear.render(popup_area, buf);
+        let mut title = format!("{} Progress", spinner(self.tick));
+        if self.queue_total > 0 {
+            title += &format!(
+                " ({}/{} queued)",
+                self.queue_total - self.queue.len(),
+                self.queue_total
+            );
+        }
+        let title = Title::from(title).alignment(Alignment::Center);
+        let title = Block::new()
+            .borders(Borders::NONE)
+            .padding(Padding::vertical(1))
+            .title(title);
+
+        let (ratio, label) = match &self.download {
+            Some((Phase::Installing, _)) => (1.0, "Installing…".to_string()),
+            Some((Phase::Downloading, download))
+                if download.done > 0 && Some(download.done) == download.total =>
+            {
+                (1.0, "Checking…".to_string())
+            }
+            Some((phase, download)) if download.done > 0 => {
+                let mut label = format!("{} · {}", phase, format_size(download.done));
+                if let Some(total) = download.total {
+                    label += &format!(" of {}", format_size(total));
+                }
+                if let Some(rate) = download.bytes_per_second() {
+                    label += &format!(" · {}/s", format_size(rate as u64));
+                }
+                if let Some(remaining) = download.remaining() {
+                    label += &format!(" · {}s left", remaining.as_secs());
+                }
+                (download.ratio(), label)
+            }
+            Some((phase, _)) => (0.0, format!("{}…", phase)),
+            None => (0.0, "Downloading…".to_string()),
+        };
+        Gauge::default()
+            .block(title)
+            .gauge_style(GAUGE_COLOR)
+            .ratio(ratio)
+            .label(label)
+            .render(popup_area, buf);
+        Block::bordered()
+            .borders(Borders::NONE)
+            .title("Progress")
+            .render(popup_area, buf);
+    }
+
+    fn render_json(&self, scroll: u16, area: Rect, buf: &mut Buffer) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        let popup_area = centered_rect(area, 80, 80);
+
+        Clear.render(popup_area, buf);
+        Paragraph::new(item.raw.as_str())
+            .block(
+                Block::bordered()
+                    .title(Title::from(item.tag_name.as_str()).alignment(Alignment::Center))
+                    .border_type(BorderType::Rounded),
+            )
+            .scroll((scroll, 0))
+            .render(popup_area, buf);
+    }
+
+    fn render_asset_picker(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(i) = self.asset_picker_release() else {
+            return;
+        };
+        let Some(picker) = self.asset_picker.as_mut() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        let items: Vec<ListItem> = item
+            .assets
+            .iter()
+            .map(|asset| {
+                let mut line = vec![
+                    Span::raw(asset.display_name()),
+                    Span::styled(
+                        format!(
+                            "  {}  {} downloads",
+                            format_size(asset.size),
+                            asset.download_count
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ];
+                if item.asset_id == asset.id {
+                    line.push(Span::styled(
+                        "  ← installs",
+                        Style::default().fg(Color::Gray),
+                    ));
+                }
+                ListItem::new(Line::from(line))
+            })
+            .collect();
+        let key = |label: &'static str| Span::styled(label, Style::default().fg(Color::LightBlue));
+        let hints = Line::from(vec![
+            key("i"),
+            " install ".into(),
+            key("Enter/d"),
+            " download ".into(),
+            key("c"),
+            " copy URL ".into(),
+            key("o"),
+            " open in browser ".into(),
+            key("Esc"),
+            " close".into(),
+        ]);
+        let popup_area = centered_rect(area, 60, 50);
+
+        Clear.render(popup_area, buf);
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(
+                        Title::from(format!("Assets of {}", item.tag_name))
+                            .alignment(Alignment::Center),
+                    )
+                    .title(
+                        Title::from(hints)
+                            .position(Position::Bottom)
+                            .alignment(Alignment::Center),
+                    )
+                    .border_type(BorderType::Rounded),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+        StatefulWidget::render(list, popup_area, buf, &mut picker.state);
+    }
+
+    fn render_device_picker(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(picker) = self.device_picker.as_mut() else {
+            return;
+        };
+        let current = self.device.as_ref().map(|device| device.serial.as_str());
+        let items: Vec<ListItem> = match &picker.error {
+            Some(error) => vec![ListItem::new(Line::styled(
+                error.as_str(),
+                Style::default().fg(Color::Red),
+            ))],
+            None if picker.refreshed.is_none() => vec![ListItem::new(Line::styled(
+                "Listing devices…",
+                Style::default().fg(Color::DarkGray),
+            ))],
+            None if picker.devices.is_empty() => vec![ListItem::new(Line::styled(
+                "No devices, plug one in or connect one",
+                Style::default().fg(Color::DarkGray),
+            ))],
+            None => picker
+                .devices
+                .iter()
+                .map(|device| {
+                    let state_color = match device.state.as_str() {
+                        "device" => Color::Green,
+                        "unauthorized" => Color::Yellow,
+                        _ => Color::Red,
+                    };
+                    let mut line = vec![
+                        Span::raw(device.serial.as_str()),
+                        Span::styled(
+                            format!("  {}", device.state),
+                            Style::default().fg(state_color),
+                        ),
+                        Span::styled(
+                            format!("  {}", device.model.as_deref().unwrap_or("-")),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ];
+                    if current == Some(device.serial.as_str()) {
+                        line.push(Span::styled(
+                            "  installing to",
+                            Style::default().fg(Color::Gray),
+                        ));
+                    }
+                    ListItem::new(Line::from(line))
+                })
+                .collect(),
+        };
+        let key = |label: &'static str| Span::styled(label, Style::default().fg(Color::LightBlue));
+        let hints = Line::from(vec![
+            key("Enter"),
+            " install to ".into(),
+            key("Esc"),
+            " close".into(),
+        ]);
+        let popup_area = centered_rect(area, 60, 50);
+
+        Clear.render(popup_area, buf);
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(Title::from("Devices").alignment(Alignment::Center))
+                    .title(
+                        Title::from(hints)
+                            .position(Position::Bottom)
+                            .alignment(Alignment::Center),
+                    )
+                    .border_type(BorderType::Rounded),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+        StatefulWidget::render(list, popup_area, buf, &mut picker.state);
+    }
+
+    fn render_actions(&mut self, area: Rect, buf: &mut Buffer) {
+        // actions
+        let keymap = &self.config.keymap;
+        let key = |label: String| Span::styled(label, Style::default().fg(Color::LightBlue));
+        let mut actions = vec![
+            key(keymap.label(Action::Next) + &keymap.label(Action::Previous)),
+            " to move ".into(),
+            key(keymap.label(Action::Unselect)),
+            " to unselect ".into(),
+            key(keymap.label(Action::Install)),
+            " to change status ".into(),
+            key(keymap.label(Action::CleanInstall)),
+            " to uninstall and install ".into(),
+            key(keymap.label(Action::BroadcastInstall)),
+            " to install to all devices ".into(),
+            key(keymap.label(Action::Undo)),
+            " to undo ".into(),
+            key(keymap.label(Action::Search)),
+            " to search ".into(),
+            key(keymap.label(Action::ToggleInstallable)),
+            " to hide uninstallable ".into(),
+            key(keymap.label(Action::MarkDiffBase)),
+            " to compare ".into(),
+            key(keymap.label(Action::ToggleView)),
+            " to toggle details ".into(),
+            key(keymap.label(Action::ToggleStatusSource)),
+            " to show recorded/on device ".into(),
+            key(keymap.label(Action::DownloadAll)),
+            " to download all assets ".into(),
+            key(keymap.label(Action::CopyInstallCommand)),
+            " to copy install command ".into(),
+            key(keymap.label(Action::CopyNotes)),
+            " to copy notes ".into(),
+            key(keymap.label(Action::CycleSort)),
+            " to sort ".into(),
+            key(keymap.label(Action::PushOnly)),
+            " to push without installing ".into(),
+            key(keymap.label(Action::PickAsset)),
+            " to pick an asset ".into(),
+            key(keymap.label(Action::ShowJson)),
+            " to show JSON ".into(),
+            key(keymap.label(Action::DeleteCached) + "/" + &keymap.label(Action::ClearCache)),
+            " to delete cached/all ".into(),
+            key(keymap.label(Action::SwitchUser)),
+            " to switch user ".into(),
+            key(keymap.label(Action::SwitchDevice) + "/" + &keymap.label(Action::PickDevice)),
+            " to switch/pick device ".into(),
+            key(keymap.label(Action::CheckDevice)),
+            " to check device ".into(),
+            key(keymap.label(Action::Top) + "/" + &keymap.label(Action::Bottom)),
+            " to go to top/bottom ".into(),
+            key(keymap.label(Action::Refresh)),
+            " to refresh ".into(),
+            key(keymap.label(Action::ToggleInventory)),
+            " to show installed apps ".into(),
+            key(keymap.label(Action::FilterLog)),
+            " to filter log ".into(),
+            key(keymap.label(Action::Enqueue) + "/" + &keymap.label(Action::RunQueue)),
+            " to queue/install queue ".into(),
+            key(keymap.label(Action::WatchLogcat)),
+            " to watch logcat ".into(),
+            key(keymap.label(Action::PauseLogcat) + "/" + &keymap.label(Action::ClearLogcat)),
+            " to pause/clear logcat ".into(),
+            key(keymap.label(Action::TogglePin)),
+            " to pin ".into(),
+            key(keymap.label(Action::JumpToTag)),
+            " to jump to tag ".into(),
+            key(keymap.label(Action::ExportReport)),
+            " to export installs ".into(),
+            key(keymap.label(Action::ToggleActions)),
+            " to hide this ".into(),
+            key(keymap.label(Action::Quit)),
+            " to quit ".into(),
+        ];
+        if area.width < NARROW_WIDTH {
+            let quit = actions.len() - 2;
+            actions.splice(
+                quit..quit,
+                [
+                    key(keymap.label(Action::ExpandInfo)),
+                    " to expand list/info ".into(),
+                ],
+            );
+        }
+
+        let device = match &self.device_check {
+            Some(Ok(device)) => Line::styled(
+                format!(" device: ready · {} ", device),
+                Style::default().fg(Color::Green),
+            ),
+            Some(Err(AppError::DeviceUnauthorized(serial))) => Line::styled(
+                format!(" device: unauthorized · {} ", serial),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(Err(_)) => Line::styled(" no device ", Style::default().fg(Color::Red)),
+            None => Line::from(" device: unchecked "),
+        };
+
+        Paragraph::new(Line::from(actions))
+            .block(
+                Block::new()
+                    .title(Title::from(device).alignment(Alignment::Right))
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .centered()
+            .render(area, buf);
+    }
     async fn run(&mut self, mut terminal: Terminal<impl Backend>) -> io::Result<()> {
+        self.check_device();
+        if self.config.watch {
+            self.start_watching();
+        }
         loop {
-            self.draw(&mut terminal)?;
-
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::*;
-                    match key.code {
-                        Char('q') | Esc => return Ok(()),
-                        Char('h') | Left => self.items.unselect(),
-                        Char('j') | Down => self.items.next(),
-                        Char('k') | Up => self.items.previous(),
-                        Char('l') | Right | Enter => self.flip_status(),
-                        Char('g') => self.go_top(),
-                        Char('G') => self.go_bottom(),
+            if self.needs_redraw || self.animating() {
+                self.tick += 1;
+                self.draw(&mut terminal)?;
+            }
+
+            while let Ok(update) = self.updates_rx.try_recv() {
+                self.update_transfer(update);
+                self.needs_redraw = true;
+            }
+            while let Ok(update) = self.broadcast_rx.try_recv() {
+                self.update_broadcast(update);
+                self.needs_redraw = true;
+            }
+            if let Some(logcat) = &mut self.logcat {
+                match logcat.receive() {
+                    Some(0) => {}
+                    Some(_) => self.needs_redraw = true,
+                    None => {
+                        let package = logcat.package.clone();
+                        self.logcat = None;
+                        self.log(format!("{} stopped, closed its logcat", package));
+                    }
+                }
+            }
+            while let Ok((source, id, result)) = self.details_rx.try_recv() {
+                self.update_details(source, id, result);
+                self.needs_redraw = true;
+            }
+            while let Ok((source, result)) = self.more_rx.try_recv() {
+                self.update_more(source, result);
+                self.needs_redraw = true;
+            }
+            while let Ok(event) = self.tasks_rx.try_recv() {
+                self.update_task(event);
+                self.needs_redraw = true;
+            }
+            while let Ok((source, id, result)) = self.checksums_rx.try_recv() {
+                self.update_checksums(source, id, result);
+                self.needs_redraw = true;
+            }
+            while let Ok(notice) = self.notices_rx.try_recv() {
+                self.warn(notice);
+            }
+            while let Ok(update) = self.watch_rx.try_recv() {
+                self.update_watch(update);
+            }
+            self.load_details();
+            self.load_checksums();
+            self.load_more();
+            self.auto_install_newest();
+            self.refresh_device_picker();
+
+            if !event::poll(TICK_RATE)? {
+                continue;
+            }
+
+            let event = event::read()?;
+            if matches!(event, Event::Resize(..)) {
+                self.needs_redraw = true;
+            }
+            if let Event::Key(key) = event {
+                if key.kind == KeyEventKind::Press && self.token_prompt.is_some() {
+                    self.handle_token_key(key.code);
+                } else if key.kind == KeyEventKind::Press && self.confirm.is_some() {
+                    self.handle_confirm_key(key.code);
+                } else if key.kind == KeyEventKind::Press && self.json_scroll.is_some() {
+                    self.handle_json_key(&key);
+                } else if key.kind == KeyEventKind::Press && self.asset_picker.is_some() {
+                    self.handle_asset_picker_key(&key);
+                } else if key.kind == KeyEventKind::Press && self.device_picker.is_some() {
+                    self.handle_device_picker_key(&key);
+                } else if key.kind == KeyEventKind::Press && self.tag_prompt.is_some() {
+                    self.handle_tag_prompt_key(key.code);
+                } else if key.kind == KeyEventKind::Press && self.searching {
+                    self.handle_search_key(key.code);
+                } else if key.kind == KeyEventKind::Press
+                    && self.notes_search.as_ref().is_some_and(|search| {
+                        search.editing
+                            || matches!(key.code, KeyCode::Char('n' | 'N') | KeyCode::Esc)
+                    })
+                {
+                    self.handle_notes_search_key(key.code);
+                } else if key.kind == KeyEventKind::Press && self.screen == Screen::Inventory {
+                    match self.config.keymap.action(&key) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(Action::Next) => self.next_package(),
+                        Some(Action::Previous) => self.previous_package(),
+                        Some(Action::Install) => self.show_matching_release(),
+                        Some(Action::Refresh) => self.load_packages(),
+                        Some(Action::FilterLog) => {
+                            self.log.cycle_filter();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::ToggleInventory) => {
+                            self.screen = Screen::Releases;
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::ExpandInfo) => {
+                            self.info_expanded = !self.info_expanded;
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::ToggleActions) => self.toggle_actions(),
                         _ => {}
                     }
+                } else if key.kind == KeyEventKind::Press {
+                    match self.config.keymap.action(&key) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(Action::Unselect) => {
+                            self.items.unselect();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::Next) => {
+                            self.items.next();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::Previous) => {
+                            self.items.previous();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::Install) => self.flip_status(),
+                        Some(Action::CleanInstall) => self.confirm_clean_install(),
+                        Some(Action::BroadcastInstall) => self.confirm_broadcast(),
+                        Some(Action::Undo) => self.undo(),
+                        Some(Action::Top) => self.go_top(),
+                        Some(Action::Bottom) => self.go_bottom(),
+                        Some(Action::SwitchUser) => self.next_user(),
+                        Some(Action::SwitchDevice) => self.next_device(),
+                        Some(Action::CheckDevice) => self.check_device(),
+                        Some(Action::Search) => {
+                            self.searching = true;
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::SearchNotes) => {
+                            self.notes_search = Some(NotesSearch {
+                                query: String::new(),
+                                editing: true,
+                                current: 0,
+                                count: 0,
+                            });
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::ToggleInstallable) => {
+                            self.items.installable_only = !self.items.installable_only;
+                            self.items.apply_filter();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::MarkDiffBase) => self.toggle_diff_base(),
+                        Some(Action::ToggleView) => self.toggle_view(),
+                        Some(Action::ToggleStatusSource) => self.toggle_status_source(),
+                        Some(Action::ExportReport) => self.export_report(),
+                        Some(Action::ExpandInfo) => {
+                            self.info_expanded = !self.info_expanded;
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::TogglePin) => self.toggle_pin(),
+                        Some(Action::JumpToTag) => {
+                            self.tag_prompt = Some(TagPrompt {
+                                input: String::new(),
+                                completion: None,
+                            });
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::DownloadAll) => self.confirm_download_all(),
+                        Some(Action::CopyInstallCommand) => self.copy_install_command(),
+                        Some(Action::PushOnly) => self.push_only(),
+                        Some(Action::CycleSort) => self.cycle_sort(),
+                        Some(Action::ToggleActions) => self.toggle_actions(),
+                        Some(Action::PickAsset) => self.open_asset_picker(),
+                        Some(Action::CopyNotes) => self.copy_notes(),
+                        Some(Action::PickDevice) => self.open_device_picker(),
+                        Some(Action::DeleteCached) => self.confirm_delete_cached(),
+                        Some(Action::ShowJson) => self.show_json(),
+                        Some(Action::ClearCache) => {
+                            self.confirm = Some(Confirmation::ClearCache);
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::Refresh) => self.refresh(),
+                        Some(Action::FilterLog) => {
+                            self.log.cycle_filter();
+                            self.needs_redraw = true;
+                        }
+                        Some(Action::Enqueue) => self.toggle_queued(),
+                        Some(Action::RunQueue) => self.confirm_run_queue(),
+                        Some(Action::WatchLogcat) => self.toggle_logcat().await,
+                        Some(Action::PauseLogcat) => {
+                            if let Some(logcat) = &mut self.logcat {
+                                logcat.paused = !logcat.paused;
+                                self.needs_redraw = true;
+                            }
+                        }
+                        Some(Action::ClearLogcat) => {
+                            if let Some(logcat) = &mut self.logcat {
+                                logcat.lines.clear();
+                                self.needs_redraw = true;
+                            }
+                        }
+                        Some(Action::ToggleInventory) => {
+                            self.screen = Screen::Inventory;
+                            self.needs_redraw = true;
+                            self.load_packages();
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            if let Some(installing) = &self.installing {
+                self.keep_installing(installing.source, installing.id);
+            } else if let Some(index) = self.items.in_progress.filter(|_| self.device_unchosen()) {
+                self.items.in_progress = None;
+                self.open_device_picker();
+                if let Some(picker) = &mut self.device_picker {
+                    picker.pending = Some(index);
+                }
+            } else if let Some(index) = self.items.in_progress {
+                if !self.start_install(index) {
+                    self.items.in_progress = None;
+                    if self.queue_total > 0 {
+                        self.queue_total = 0;
+                        self.warn(format!(
+                            "Stopped the queue, {} releases left",
+                            self.queue.len()
+                        ));
+                    }
+                }
+            } else if self.queue_total > 0 && self.confirm.is_none() {
+                self.next_in_queue();
+            }
+        }
+    }
+
+    /// Answers the pending confirmation, carrying out what it asked about on `y` and cancelling it
+    /// on any other key.
+    fn handle_confirm_key(&mut self, code: KeyCode) {
+        self.needs_redraw = true;
+        let Some(confirmation) = self.confirm.take() else {
+            return;
+        };
+        if code != KeyCode::Char('y') {
+            self.log("Cancelled".to_string());
+            return;
+        }
+
+        match confirmation {
+            Confirmation::Reinstall(index) => {
+                self.items.in_progress = Some(index);
+                self.replace.uninstall_first = true;
+            }
+            Confirmation::Stale { index, .. } => self.flip_status_at(index),
+            Confirmation::Broadcast { index, .. } => self.broadcast_install(index),
+            Confirmation::DownloadAll { index, .. } => self.download_all(index),
+            Confirmation::RunQueue { .. } => self.run_queue(),
+            Confirmation::CleanInstall { index, .. } => {
+                self.items.items[index].status = Status::Installed;
+                self.items.in_progress = Some(index);
+                self.replace.uninstall_first = true;
+            }
+            Confirmation::Downgrade { index, .. } => {
+                self.items.in_progress = Some(index);
+                self.replace.allow_downgrade = true;
+            }
+            Confirmation::DeleteCached {
+                source,
+                asset_id,
+                tag_name,
+            } => match self.cache(source).remove(asset_id) {
+                Ok(freed) => self.log(format!(
+                    "Deleted the cached APK of {}, freed {}",
+                    tag_name,
+                    format_size(freed)
+                )),
+                Err(error) => self.error(format!("Could not delete the cached APK! {}", error)),
+            },
+            Confirmation::ClearCache => {
+                let cleared: io::Result<Vec<u64>> = (0..self.sources.len())
+                    .map(|source| self.cache(source).clear())
+                    .collect();
+                match cleared {
+                    Ok(freed) => self.log(format!(
+                        "Cleared the cache, freed {}",
+                        format_size(freed.iter().sum())
+                    )),
+                    Err(error) => self.error(format!("Could not clear the cache! {}", error)),
+                }
+            }
+        }
+    }
+
+    /// Opens the raw JSON of the selected release.
+    fn show_json(&mut self) {
+        if self.items.selected().is_some() {
+            self.json_scroll = Some(0);
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Scrolls or closes the raw JSON popup.
+    fn handle_json_key(&mut self, key: &KeyEvent) {
+        self.needs_redraw = true;
+        let Some(scroll) = self.json_scroll else {
+            return;
+        };
+        self.json_scroll = match self.config.keymap.action(key) {
+            Some(Action::Next) => Some(scroll.saturating_add(1)),
+            Some(Action::Previous) => Some(scroll.saturating_sub(1)),
+            Some(Action::Top) => Some(0),
+            _ if key.code == KeyCode::PageDown => Some(scroll.saturating_add(10)),
+            _ if key.code == KeyCode::PageUp => Some(scroll.saturating_sub(10)),
+            _ => None,
+        };
+    }
+
+    /// Lists the assets of the selected release to download, copy or open one of them.
+    fn open_asset_picker(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if !matches!(item.details, Details::Complete) {
+            self.warn(format!(
+                "The assets of {} are not loaded yet.",
+                item.tag_name
+            ));
+            return;
+        }
+        if item.assets.is_empty() {
+            self.warn(format!("{} has no assets.", item.tag_name));
+            return;
+        }
+        self.asset_picker = Some(AssetPicker {
+            source: item.source,
+            id: item.id,
+            state: ListState::default().with_selected(Some(0)),
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Index of the release the asset picker is open for. Closes the picker if the release is no
+    /// longer listed or has no assets, e.g. after a refresh.
+    fn asset_picker_release(&mut self) -> Option<usize> {
+        let picker = self.asset_picker.as_ref()?;
+        let index = self.items.items.iter().position(|item| {
+            item.source == picker.source && item.id == picker.id && !item.assets.is_empty()
+        });
+        if index.is_none() {
+            self.asset_picker = None;
+        }
+        index
+    }
+
+    /// Moves through the asset picker or acts on the picked asset, closing the picker.
+    fn handle_asset_picker_key(&mut self, key: &KeyEvent) {
+        self.needs_redraw = true;
+        let Some(i) = self.asset_picker_release() else {
+            return;
+        };
+        let Some(AssetPicker { state, .. }) = self.asset_picker.as_mut() else {
+            return;
+        };
+        let count = self.items.items[i].assets.len();
+        let selected = state.selected().unwrap_or(0);
+        match self.config.keymap.action(key) {
+            Some(Action::Next) => state.select(Some((selected + 1) % count)),
+            Some(Action::Previous) => {
+                state.select(Some(selected.checked_sub(1).unwrap_or(count - 1)))
+            }
+            Some(Action::Top) => state.select(Some(0)),
+            Some(Action::Bottom) => state.select(Some(count - 1)),
+            _ => {
+                let Some(asset) = self.items.items[i].assets.get(selected).cloned() else {
+                    return;
+                };
+                match key.code {
+                    KeyCode::Char('i') => {
+                        self.asset_picker = None;
+                        self.install_picked(i, asset);
+                        return;
+                    }
+                    KeyCode::Enter | KeyCode::Char('d') => self.download_assets(i, vec![asset]),
+                    KeyCode::Char('c') => match clipboard::copy(&asset.browser_download_url) {
+                        Ok(()) => self.log(format!("Copied: {}", asset.browser_download_url)),
+                        Err(error) => self.warn(format!("Could not copy the URL! {}", error)),
+                    },
+                    KeyCode::Char('o') => match browser::open(&asset.browser_download_url) {
+                        Ok(()) => self.log(format!("Opened {}", asset.browser_download_url)),
+                        Err(error) => self.error(format!(
+                            "Could not open {}! {}",
+                            asset.browser_download_url, error
+                        )),
+                    },
+                    KeyCode::Esc | KeyCode::Char('q') => {}
+                    _ => return,
+                }
+                self.asset_picker = None;
+            }
+        }
+    }
+
+    /// Installs the asset picked by hand, and from now on whenever the release is installed.
+    fn install_picked(&mut self, index: usize, asset: Asset) {
+        let item = &mut self.items.items[index];
+        item.asset_id = asset.id;
+        item.asset = Some(asset);
+        item.asset_picked = true;
+        match item.status {
+            Status::Open => self.flip_status_of(index),
+            // installs again, keeping the status
+            Status::Installed => self.items.in_progress = Some(index),
+        }
+    }
+
+    /// Lists the devices in the background to install the release at `index` to, asking first
+    /// unless `confirmed`.
+    fn list_broadcast_devices(&self, index: usize, confirmed: bool) {
+        self.spawn_adb(move || TaskEvent::BroadcastDevices {
+            index,
+            confirmed,
+            devices: adb::ensure_server().and_then(|()| Ok(adb::devices()?)),
+        });
+    }
+
+    /// The ready ones of the listed devices, or `None` after reporting why there are none.
+    fn ready_devices(
+        &mut self,
+        devices: std::result::Result<Vec<Device>, AppError>,
+    ) -> Option<Vec<Device>> {
+        match devices {
+            Ok(devices) => {
+                let ready: Vec<Device> = devices.iter().filter(|d| d.is_ready()).cloned().collect();
+                if ready.is_empty() {
+                    self.device_error(adb::not_ready(&devices));
+                    return None;
+                }
+                Some(ready)
+            }
+            Err(error) => {
+                self.error(format!("Could not list devices! {}", error));
+                None
+            }
+        }
+    }
+
+    /// Asks before installing the selected release to every ready device.
+    fn confirm_broadcast(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        if self.broadcast_tag.is_some() {
+            self.warn("A broadcast install is still running.".to_string());
+            return;
+        }
+        let item = &self.items.items[i];
+        if !matches!(item.details, Details::Complete) {
+            self.warn(format!(
+                "The assets of {} are not loaded yet.",
+                item.tag_name
+            ));
+            return;
+        }
+        if item.asset.is_none() {
+            self.warn("No APK asset found in the selected release.".to_string());
+            return;
+        }
+        self.list_broadcast_devices(i, false);
+    }
+
+    /// Asks before installing the release at `index` to the ready `devices`.
+    fn ask_broadcast(&mut self, index: usize, devices: Vec<Device>) {
+        let Some(item) = self.items.items.get(index) else {
+            return;
+        };
+        let Some(asset) = &item.asset else {
+            return;
+        };
+        let tag_name = item.tag_name.clone();
+        let asset = (asset.name.clone(), asset.size);
+        self.needs_redraw = true;
+        self.confirm = Some(Confirmation::Broadcast {
+            index,
+            tag_name,
+            asset,
+            devices: devices.iter().map(Device::to_string).collect(),
+            stale: self.stale(index),
+        });
+    }
+
+    /// Installs the release at `index` to every device ready once confirmed.
+    fn broadcast_install(&self, index: usize) {
+        self.list_broadcast_devices(index, true);
+    }
+
+    /// Installs the release at `index` to the ready `devices` in the background.
+    fn install_to_all(&mut self, index: usize, devices: Vec<Device>) {
+        let Some(item) = self.items.items.get(index) else {
+            return;
+        };
+        let Some(asset) = item.asset.clone() else {
+            return;
+        };
+        let source = &self.sources[item.source];
+        let github = source.github.clone();
+        let config = self.config.for_repo(&source.repo);
+        let tag_name = item.tag_name.clone();
+        let assets = item.assets.clone();
+        self.broadcast_release = Some((source.repo.to_string(), asset.name.clone()));
+
+        self.log(format!(
+            "Installing {} to {} devices",
+            tag_name,
+            devices.len()
+        ));
+        self.broadcast = devices
+            .iter()
+            .map(|device| DeviceInstall {
+                device: device.clone(),
+                state: InstallState::Downloading,
+            })
+            .collect();
+        self.broadcast_tag = Some(tag_name);
+        broadcast::install_all(
+            github,
+            config,
+            asset,
+            assets,
+            devices,
+            self.broadcast_tx.clone(),
+        );
+    }
+
+    /// Asks before uninstalling the app with its data and installing the selected release.
+    fn confirm_clean_install(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if item.asset.is_none() {
+            self.warn("No APK asset found in the selected release.".to_string());
+            return;
+        }
+        self.needs_redraw = true;
+        self.confirm = Some(Confirmation::CleanInstall {
+            index: i,
+            tag_name: item.tag_name.clone(),
+            stale: self.stale(i),
+        });
+    }
+
+    /// Asks whether to delete the cached APK of the selected release.
+    fn confirm_delete_cached(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if !self.cache(item.source).contains(item.asset_id) {
+            self.log(format!("{} is not cached", item.tag_name));
+            return;
+        }
+        self.needs_redraw = true;
+        self.confirm = Some(Confirmation::DeleteCached {
+            source: item.source,
+            asset_id: item.asset_id,
+            tag_name: item.tag_name.clone(),
+        });
+    }
+
+    /// Asks for a new token to retry the operation GitHub refused with it.
+    fn ask_token(&mut self, retry: Retry) {
+        self.warn("GitHub rejected the token (401 Unauthorized), enter a new one".to_string());
+        self.token_prompt = Some((String::new(), retry));
+    }
+
+    /// Edits the new token and retries the refused operation with it on Enter.
+    fn handle_token_key(&mut self, code: KeyCode) {
+        self.needs_redraw = true;
+        let Some((input, _)) = &mut self.token_prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.token_prompt = None;
+                self.log("Cancelled".to_string());
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter => {
+                let Some((token, retry)) = self.token_prompt.take() else {
+                    return;
+                };
+                let token = token.trim().to_string();
+                if token.is_empty() {
+                    self.log("Cancelled".to_string());
+                    return;
+                }
+                for source in &mut self.sources {
+                    source.github.set_token(token.clone());
+                }
+                self.config.token = Some(token);
+                self.log("Using the new token for this session".to_string());
+
+                match retry {
+                    Retry::Refresh => self.refresh(),
+                    Retry::Install(index) => self.items.in_progress = Some(index),
+                    Retry::CopyInstallCommand => self.copy_install_command(),
+                    Retry::Details { source, id } => {
+                        if let Some(item) = self
+                            .items
+                            .items
+                            .iter_mut()
+                            .find(|item| item.source == source && item.id == id)
+                        {
+                            item.details = Details::Pending;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Edits the filter while the search box is focused.
+    fn handle_search_key(&mut self, code: KeyCode) {
+        self.needs_redraw = true;
+        match code {
+            KeyCode::Esc => {
+                self.searching = false;
+                self.items.filter.clear();
+                self.items.apply_filter();
+            }
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Down => self.items.next(),
+            KeyCode::Up => self.items.previous(),
+            KeyCode::Backspace => {
+                self.items.filter.pop();
+                self.items.apply_filter();
+            }
+            KeyCode::Char(c) => {
+                self.items.filter.push(c);
+                self.items.apply_filter();
+            }
+            _ => {}
+        }
+    }
+
+    /// Edits the tag to jump to, completing it with Tab.
+    fn handle_tag_prompt_key(&mut self, code: KeyCode) {
+        self.needs_redraw = true;
+        let Some(prompt) = &mut self.tag_prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.tag_prompt = None,
+            KeyCode::Enter => {
+                let tag = prompt.input.trim().to_string();
+                self.tag_prompt = None;
+                if !tag.is_empty() {
+                    self.jump_to_tag(&tag);
+                }
+            }
+            KeyCode::Tab => {
+                let typed = match &prompt.completion {
+                    Some((typed, _)) => typed.clone(),
+                    None => prompt.input.clone(),
+                };
+                // newest first, once even if several repositories share a tag
+                let mut seen = HashSet::new();
+                let tags: Vec<&str> = self
+                    .items
+                    .items
+                    .iter()
+                    .map(|item| item.tag_name.as_str())
+                    .filter(|tag| tag.starts_with(&typed) && seen.insert(*tag))
+                    .collect();
+                if tags.is_empty() {
+                    return;
                 }
+                let prefix = common_prefix(&tags);
+                if prompt.completion.is_none() && prefix.len() > typed.len() {
+                    // complete as far as the tags agree before cycling through them
+                    prompt.input = prefix.to_string();
+                } else {
+                    let index = match &prompt.completion {
+                        Some((_, index)) => (index + 1) % tags.len(),
+                        None => 0,
+                    };
+                    prompt.input = tags[index].to_string();
+                    prompt.completion = Some((typed, index));
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+                prompt.completion = None;
+            }
+            KeyCode::Char(c) => {
+                prompt.input.push(c);
+                prompt.completion = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Selects the release with exactly this tag, clearing the filters hiding it.
+    fn jump_to_tag(&mut self, input: &str) {
+        let selector = Selector::parse(input);
+        // newest first
+        let Some(index) = self
+            .items
+            .items
+            .iter()
+            .position(|item| selector.matches(&item.tag_name, &item.target_commitish))
+        else {
+            self.error(format!("No release {}", selector));
+            return;
+        };
+        let (source, id) = (self.items.items[index].source, self.items.items[index].id);
+        if !self.items.visible.iter().any(|m| m.index == index) {
+            self.searching = false;
+            self.items.filter.clear();
+            self.items.installable_only = false;
+            self.items.apply_filter();
+        }
+        self.items.select_release(source, id);
+    }
+
+    /// Edits the query of the notes search, or jumps between its matches with `n` / `N`.
+    fn handle_notes_search_key(&mut self, code: KeyCode) {
+        self.needs_redraw = true;
+        let Some(search) = &mut self.notes_search else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.notes_search = None,
+            KeyCode::Enter if search.query.is_empty() => self.notes_search = None,
+            KeyCode::Enter => search.editing = false,
+            KeyCode::Backspace if search.editing => {
+                search.query.pop();
+                search.current = 0;
+            }
+            KeyCode::Char(c) if search.editing => {
+                search.query.push(c);
+                search.current = 0;
+            }
+            KeyCode::Char('n') if search.count > 0 => {
+                search.current = (search.current + 1) % search.count;
+            }
+            KeyCode::Char('N') if search.count > 0 => {
+                search.current = (search.current + search.count - 1) % search.count;
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts downloading and installing the APK asset of the release at `index` in the
+    /// background, returns whether it started.
+    fn start_install(&mut self, index: usize) -> bool {
+        let item = &self.items.items[index];
+        let tag_name = item.tag_name.clone();
+        let asset = item.asset.clone();
+        let asset_picked = item.asset_picked;
+        let assets = item.assets.clone();
+
+        if !matches!(item.details, Details::Complete) {
+            self.warn(format!("The assets of {} are not loaded yet.", tag_name));
+            return false;
+        }
+        let Some(asset) = asset else {
+            self.warn("No APK asset found in the selected release.".to_string());
+            return false;
+        };
+
+        let device = match self.device() {
+            Ok(device) => device,
+            Err(error) => {
+                self.device_error(error);
+                return false;
+            }
+        };
+
+        let mut replace = std::mem::take(&mut self.replace);
+        replace.allow_downgrade |= self.config.allow_downgrade;
+
+        // owned copies, so the install can run while the UI keeps going
+        let item = &self.items.items[index];
+        let source = &self.sources[item.source];
+        let github = source.github.clone();
+        let config = self.config.for_repo(&source.repo);
+        let loaded = match &item.checksums {
+            Checksums::Loaded(hashes) => Some(hashes.clone()),
+            _ => None,
+        };
+        self.installing = Some(Installing {
+            source: item.source,
+            id: item.id,
+            tag_name: tag_name.clone(),
+            asset: asset.name.clone(),
+            repo: source.repo.to_string(),
+            device: device.clone(),
+        });
+
+        let events = self.tasks_tx.clone();
+        tokio::spawn(async move {
+            let on_progress = |phase, done, total| {
+                let _ = events.send(TaskEvent::Progress(phase, done, total));
+            };
+            let on_warning = |message| {
+                let _ = events.send(TaskEvent::Message(Severity::Warn, message));
+            };
+            let result = async {
+                // not loaded yet if the release was not selected for long
+                let hashes = match loaded {
+                    Some(hashes) => hashes,
+                    None => checksums::load(&github, &assets).await?,
+                };
+                if config.auto_abi && !asset_picked {
+                    install::install_auto_abi(
+                        &github,
+                        &config,
+                        &device.serial,
+                        &assets,
+                        &hashes,
+                        replace,
+                        on_progress,
+                        on_warning,
+                    )
+                    .await
+                    .map(|(path, name)| (path, Some(name)))
+                } else {
+                    install_asset(
+                        &github,
+                        &config,
+                        &device.serial,
+                        &asset,
+                        hashes.get(&asset.name).map(String::as_str),
+                        replace,
+                        on_progress,
+                        on_warning,
+                    )
+                    .await
+                    .map(|path| (path, None))
+                }
+            }
+            .await;
+            match result {
+                Ok((path, picked)) => {
+                    let apk = apk::inspect(&path).ok();
+                    let package = apk.as_ref().map(|apk| apk.package.clone());
+                    let installed_asset = picked.clone().unwrap_or_else(|| asset.name.clone());
+                    let installed = Installed {
+                        picked,
+                        sha256: report::sha256(&path).ok(),
+                        apk,
+                    };
+                    let _ = events.send(TaskEvent::Installed(Ok(installed)));
+                    after_install(
+                        &config,
+                        &path,
+                        package.as_deref(),
+                        &tag_name,
+                        &installed_asset,
+                        &device.serial,
+                        &events,
+                    )
+                    .await;
+                }
+                Err(error) => {
+                    let _ = events.send(TaskEvent::Installed(Err(error)));
+                }
+            }
+            let _ = events.send(TaskEvent::InstallDone);
+        });
+        true
+    }
+
+    /// Turns down installing another release while one is installed, which is asked for with
+    /// the release list usable meanwhile.
+    fn keep_installing(&mut self, source: usize, id: i64) {
+        let running = self
+            .items
+            .items
+            .iter()
+            .position(|item| item.source == source && item.id == id);
+        let Some(index) = self
+            .items
+            .in_progress
+            .filter(|index| Some(*index) != running)
+        else {
+            return;
+        };
+        self.items.in_progress = running;
+        // meant for the release turned down
+        self.replace = Replace::default();
+        self.warn(format!(
+            "Still installing another release, {} was not installed",
+            self.items.items[index].tag_name
+        ));
+    }
+
+    /// Shows what a background task reports.
+    fn update_task(&mut self, event: TaskEvent) {
+        match event {
+            TaskEvent::Progress(phase, done, total) => {
+                let download = match &mut self.download {
+                    Some((current, download)) if *current == phase => download,
+                    // the rate of the previous phase says nothing about this one
+                    _ => &mut self.download.insert((phase, Throughput::default())).1,
+                };
+                download.record(done, total);
+            }
+            TaskEvent::Message(severity, message) => self.log.push(severity, message),
+            TaskEvent::Installed(result) => {
+                let installed = self.finish_install(result);
+                if !installed && self.queue_total > 0 {
+                    self.queue_total = 0;
+                    self.warn(format!(
+                        "Stopped the queue, {} releases left",
+                        self.queue.len()
+                    ));
+                }
+            }
+            TaskEvent::InstallDone => {
+                self.installing = None;
+                self.items.in_progress = None;
+                self.download = None;
+            }
+            TaskEvent::Pushed {
+                asset,
+                device,
+                result,
+            } => match result {
+                Ok(path) => self.log(format!("Pushed {} to {} on {}", asset, path, device)),
+                Err(error) => self.error(format!("Could not push {}! {}", asset, error)),
+            },
+            TaskEvent::InstallCommand(result) => match result {
+                Ok(command) => match clipboard::copy(&command) {
+                    Ok(()) => self.log(format!("Copied: {}", command)),
+                    Err(error) => self.warn(format!("Could not copy {}! {}", command, error)),
+                },
+                Err(error) if error.is_unauthorized() => self.ask_token(Retry::CopyInstallCommand),
+                Err(error) => self.error(format!("Could not download the APK! {}", error)),
+            },
+            TaskEvent::Refreshed(result) => {
+                self.refreshing = false;
+                self.update_refresh(result);
+            }
+            TaskEvent::Devices(result) => self.update_device_picker(result),
+            TaskEvent::DeviceChecked(result) => self.update_device_check(result),
+            TaskEvent::DeviceUnchosen => {
+                self.log("Several devices are connected, pick the one to install to".to_string());
+                self.open_device_picker();
+            }
+            TaskEvent::NextDevice(result) => self.switch_to_next_device(result),
+            TaskEvent::Switched {
+                device,
+                warnings,
+                sdk,
+                pending,
+            } => {
+                for warning in warnings {
+                    self.warn(warning);
+                }
+                self.update_switch(device, sdk, pending);
+            }
+            TaskEvent::Users(result) => match result {
+                Ok(users) => {
+                    self.users = users;
+                    self.cycle_user();
+                }
+                Err(error) => self.error(format!("Could not list users on device! {}", error)),
+            },
+            TaskEvent::Packages(result) => self.update_packages(result),
+            TaskEvent::MatchingVersion { package, version } => {
+                self.select_matching_release(package, version)
+            }
+            TaskEvent::VersionOnDevice { package, version } => {
+                self.update_on_device(package, version)
+            }
+            TaskEvent::BroadcastDevices {
+                index,
+                confirmed,
+                devices,
+            } => {
+                let Some(devices) = self.ready_devices(devices) else {
+                    return;
+                };
+                if confirmed {
+                    self.install_to_all(index, devices);
+                } else {
+                    self.ask_broadcast(index, devices);
+                }
+            }
+        }
+    }
+
+    /// Records and reports the install that finished, returns whether it worked.
+    fn finish_install(&mut self, result: std::result::Result<Installed, AppError>) -> bool {
+        let Some(installing) = &self.installing else {
+            return false;
+        };
+        let (source, id) = (installing.source, installing.id);
+        let tag_name = installing.tag_name.clone();
+        let asset = installing.asset.clone();
+        let repo = installing.repo.clone();
+        let device = installing.device.clone();
+        let picked = result
+            .as_ref()
+            .ok()
+            .and_then(|installed| installed.picked.clone());
+        if let Some(name) = &picked {
+            self.log(format!("Picked {} for the ABIs of {}", name, device));
+        }
+        self.installs.push(InstallRecord {
+            time: Utc::now(),
+            repo,
+            tag: tag_name.clone(),
+            asset: picked.unwrap_or(asset),
+            sha256: result
+                .as_ref()
+                .ok()
+                .and_then(|installed| installed.sha256.clone()),
+            serial: device.serial.clone(),
+            result: match &result {
+                Ok(_) => "installed".to_string(),
+                Err(error) => error.to_string(),
+            },
+        });
+        let installed = result.is_ok();
+        // the releases may have been refreshed meanwhile
+        let Some(index) = self
+            .items
+            .items
+            .iter()
+            .position(|item| item.source == source && item.id == id)
+        else {
+            if let Err(error) = result {
+                self.error(format!(
+                    "Failed to install {} to {}: {}",
+                    tag_name, device, error
+                ));
+            }
+            return installed;
+        };
+        match result {
+            Ok(installed) => {
+                if let Some(apk) = installed.apk {
+                    self.items.items[index].sdk = Some((apk.min_sdk, apk.target_sdk));
+                    self.installed_package = Some(apk.package);
+                }
+                // a completed install can not be undone
+                let (source, id) = (self.items.items[index].source, self.items.items[index].id);
+                self.history
+                    .retain(|change| change.source != source || change.id != id);
+                self.log(format!(
+                    "Installed {} to {} for user {}",
+                    tag_name, device, self.config.user_id
+                ));
+            }
+            Err(error @ AppError::SignatureMismatch(..)) => {
+                self.warn(error.to_string());
+                self.needs_redraw = true;
+                self.confirm = Some(Confirmation::Reinstall(index));
+            }
+            Err(AppError::Downgrade {
+                package,
+                installed,
+                apk,
+            }) => {
+                self.warn(format!(
+                    "Device has versionCode {} of {}, {} is {}",
+                    installed, package, tag_name, apk
+                ));
+                self.needs_redraw = true;
+                self.confirm = Some(Confirmation::Downgrade {
+                    index,
+                    package,
+                    installed,
+                    apk,
+                });
+            }
+            Err(error) if error.is_unauthorized() => self.ask_token(Retry::Install(index)),
+            Err(error @ AppError::DeviceUnauthorized(_)) => self.device_error(error),
+            Err(error) => self.error(format!(
+                "Failed to install {} to {}: {}",
+                tag_name, device, error
+            )),
+        }
+        installed
+    }
+
+    /// Adds the selected release to the install queue, or removes it if it is queued already.
+    fn toggle_queued(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        let key = (item.source, item.id);
+        let tag_name = item.tag_name.clone();
+        if let Some(position) = self.queue.iter().position(|queued| *queued == key) {
+            self.queue.remove(position);
+            self.log(format!("Removed {} from the queue", tag_name));
+        } else {
+            self.log(format!("Queued {} as #{}", tag_name, self.queue.len() + 1));
+            self.queue.push_back(key);
+        }
+    }
+
+    /// Asks before installing the queued releases, listing them with the size of their APKs.
+    fn confirm_run_queue(&mut self) {
+        if self.queue.is_empty() {
+            self.warn("The queue is empty.".to_string());
+            return;
+        }
+        if self.queue_total > 0 {
+            return;
+        }
+        let device = match self.device() {
+            Ok(device) => device.to_string(),
+            Err(error) => {
+                self.device_error(error);
+                return;
+            }
+        };
+        let releases = self
+            .queue
+            .iter()
+            .filter_map(|(source, id)| {
+                self.items
+                    .items
+                    .iter()
+                    .find(|item| item.source == *source && item.id == *id)
+            })
+            .map(|item| {
+                let asset = item
+                    .asset
+                    .as_ref()
+                    .map(|asset| (asset.name.clone(), asset.size));
+                (item.tag_name.clone(), asset)
+            })
+            .collect();
+        self.needs_redraw = true;
+        self.confirm = Some(Confirmation::RunQueue { releases, device });
+    }
+
+    /// Starts installing the queued releases in order.
+    fn run_queue(&mut self) {
+        if self.queue.is_empty() {
+            self.warn("The queue is empty.".to_string());
+        } else if self.queue_total == 0 {
+            self.queue_total = self.queue.len();
+            self.log(format!("Installing {} queued releases", self.queue_total));
+        }
+    }
+
+    /// Starts installing the next queued release, finishing the queue when none is left.
+    fn next_in_queue(&mut self) {
+        let Some((source, id)) = self.queue.pop_front() else {
+            self.log(format!(
+                "Installed all {} queued releases",
+                self.queue_total
+            ));
+            self.queue_total = 0;
+            return;
+        };
+        match self
+            .items
+            .items
+            .iter()
+            .position(|item| item.source == source && item.id == id)
+        {
+            Some(i) => {
+                self.items.items[i].status = Status::Installed;
+                self.items.in_progress = Some(i);
+            }
+            None => self.warn("Skipped a queued release, it is no longer listed".to_string()),
+        }
+    }
+
+    /// Position of the release in the queue, starting at 1.
+    fn queue_position(&self, item: &ReleaseItem) -> Option<usize> {
+        self.queue
+            .iter()
+            .position(|(source, id)| *source == item.source && *id == item.id)
+            .map(|position| position + 1)
+    }
+
+    /// Launches the last installed app and shows its logcat, or closes the logcat.
+    async fn toggle_logcat(&mut self) {
+        if let Some(logcat) = self.logcat.take() {
+            self.log(format!("Closed the logcat of {}", logcat.package));
+            return;
+        }
+        let Some(package) = self.installed_package.clone() else {
+            self.warn("Install a release first to watch its logcat.".to_string());
+            return;
+        };
+        let device = match self.device() {
+            Ok(device) => device,
+            Err(error) => {
+                self.device_error(error);
+                return;
+            }
+        };
+
+        match Logcat::start(&device.serial, &package).await {
+            Ok(logcat) => {
+                self.log(format!("Launched {} (pid {})", package, logcat.pid));
+                self.logcat = Some(logcat);
+            }
+            Err(error) => self.error(format!("Could not watch {}! {}", package, error)),
+        }
+    }
+
+    /// Returns the device to install to, picking the configured or first ready one if none was
+    /// chosen.
+    fn device(&mut self) -> std::result::Result<Device, AppError> {
+        if let Some(device) = &self.device {
+            return Ok(device.clone());
+        }
+
+        let device = install::open_session(&self.config)?;
+        self.device = Some(device.clone());
+        Ok(device)
+    }
+
+    /// Makes sure the ADB server is reachable and the device to install to is ready, choosing one
+    /// if none was yet or letting it be picked when several are connected.
+    fn check_device(&mut self) {
+        let device = self.device.clone();
+        let unset = self.device_unset();
+        let config = self.config.clone();
+        self.spawn_adb(move || {
+            if unset && several_ready() {
+                return TaskEvent::DeviceUnchosen;
+            }
+            let result = match device {
+                Some(device) => adb::check_device(&device.serial),
+                None => install::open_session(&config),
+            };
+            TaskEvent::DeviceChecked(result.map(|device| {
+                let sdk = adb::sdk(Some(&device.serial)).ok().flatten();
+                (device, sdk)
+            }))
+        });
+    }
+
+    /// Reports whether the device to install to is ready, installing to it from now on if none was
+    /// chosen yet.
+    fn update_device_check(
+        &mut self,
+        result: std::result::Result<(Device, Option<u32>), AppError>,
+    ) {
+        match result {
+            Ok((device, sdk)) => {
+                self.device_sdk = sdk;
+                self.log(format!("Device ready: {}", device));
+                self.device_check = Some(Ok(device.to_string()));
+                self.device.get_or_insert(device);
+            }
+            Err(error @ AppError::DeviceUnauthorized(_)) => self.device_error(error),
+            Err(error) => {
+                self.warn(error.to_string());
+                self.device_check = Some(Err(error));
+            }
+        }
+    }
+
+    /// Lists the devices to pick the one to install to, starting the ADB server if needed.
+    fn open_device_picker(&mut self) {
+        self.device_picker = Some(DevicePicker {
+            devices: Vec::new(),
+            state: ListState::default(),
+            refreshed: None,
+            listing: false,
+            error: None,
+            pending: None,
+        });
+        self.needs_redraw = true;
+        self.refresh_device_picker();
+    }
+
+    /// Whether several devices are ready and none was chosen yet, so the one to install to has to
+    /// be picked rather than taking the first.
+    fn device_unchosen(&self) -> bool {
+        self.device_unset() && several_ready()
+    }
+
+    /// Whether no device was chosen or configured to install to.
+    fn device_unset(&self) -> bool {
+        self.device.is_none() && self.config.serial.is_none() && self.config.connect.is_none()
+    }
+
+    /// Lists the devices again in the background while the picker is open, so plugged in devices
+    /// show up.
+    fn refresh_device_picker(&mut self) {
+        let Some(picker) = &mut self.device_picker else {
+            return;
+        };
+        if picker.listing
+            || picker
+                .refreshed
+                .is_some_and(|refreshed| refreshed.elapsed() < DEVICE_REFRESH)
+        {
+            return;
+        }
+        picker.listing = true;
+        self.spawn_adb(|| {
+            // the server is started along with the first listing
+            TaskEvent::Devices(adb::ensure_server().and_then(|()| Ok(adb::devices()?)))
+        });
+    }
+
+    /// Shows the devices listed in the background, keeping the selected one selected.
+    fn update_device_picker(&mut self, result: std::result::Result<Vec<Device>, AppError>) {
+        let Some(picker) = &mut self.device_picker else {
+            return;
+        };
+        picker.listing = false;
+        picker.refreshed = Some(Instant::now());
+        let selected = picker
+            .state
+            .selected()
+            .and_then(|i| picker.devices.get(i))
+            .map(|device| device.serial.clone())
+            .or_else(|| self.device.as_ref().map(|device| device.serial.clone()));
+        match result {
+            Ok(devices) => {
+                picker.devices = devices;
+                picker.error = None;
+            }
+            Err(error) => picker.error = Some(format!("Could not list devices! {}", error)),
+        }
+        let index = selected
+            .and_then(|serial| picker.devices.iter().position(|d| d.serial == serial))
+            .unwrap_or(0);
+        picker
+            .state
+            .select((!picker.devices.is_empty()).then_some(index));
+    }
+
+    /// Moves through the device picker, installs to the selected device on Enter.
+    fn handle_device_picker_key(&mut self, key: &KeyEvent) {
+        self.needs_redraw = true;
+        let Some(picker) = &mut self.device_picker else {
+            return;
+        };
+        let count = picker.devices.len();
+        let selected = picker.state.selected();
+        let pending = picker.pending;
+        match (self.config.keymap.action(key), selected) {
+            (Some(Action::Next), Some(i)) => picker.state.select(Some((i + 1) % count)),
+            (Some(Action::Previous), Some(i)) => picker
+                .state
+                .select(Some(i.checked_sub(1).unwrap_or(count - 1))),
+            _ => match key.code {
+                KeyCode::Enter => {
+                    let Some(device) = selected.and_then(|i| picker.devices.get(i)).cloned() else {
+                        return;
+                    };
+                    let current = self.device.as_ref().map(|device| device.serial.as_str());
+                    match device.ready() {
+                        Ok(device) if current == Some(device.serial.as_str()) => {
+                            self.device_picker = None;
+                            self.log(format!("Already installing to {}", device));
+                            self.items.in_progress = pending;
+                        }
+                        Ok(device) => {
+                            self.device_picker = None;
+                            self.switch_device(device, pending);
+                        }
+                        Err(error) => self.device_error(error),
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.device_picker = None;
+                    if pending.is_some() {
+                        self.warn("No device picked, did not install".to_string());
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Switches to the next ready device.
+    fn next_device(&mut self) {
+        self.spawn_adb(|| {
+            TaskEvent::NextDevice(adb::ensure_server().and_then(|()| Ok(adb::devices()?)))
+        });
+    }
+
+    /// Switches to the ready device listed after the current one.
+    fn switch_to_next_device(&mut self, result: std::result::Result<Vec<Device>, AppError>) {
+        let all = match result {
+            Ok(devices) => devices,
+            Err(error) => {
+                self.error(format!("Could not list devices! {}", error));
+                return;
+            }
+        };
+        let devices: Vec<Device> = all.iter().filter(|d| d.is_ready()).cloned().collect();
+
+        let position = self
+            .device
+            .as_ref()
+            .and_then(|current| devices.iter().position(|d| d.serial == current.serial))
+            .map_or(0, |p| (p + 1) % devices.len());
+        match devices.into_iter().nth(position) {
+            Some(device) => self.switch_device(device, None),
+            None => self.device_error(adb::not_ready(&all)),
+        }
+    }
+
+    /// Installs to the device from now on, then the release at `pending` if there is one.
+    fn switch_device(&mut self, device: Device, pending: Option<usize>) {
+        let previous = self.device.clone();
+        let config = self.config.clone();
+        self.spawn_adb(move || {
+            // the port mappings move along to the new device
+            let warnings = previous
+                .map(|previous| install::close_port_mappings(&config, &previous.serial))
+                .unwrap_or_default();
+            let sdk = install::open_port_mappings(&config, &device.serial)
+                .map(|()| adb::sdk(Some(&device.serial)).ok().flatten());
+            TaskEvent::Switched {
+                device,
+                warnings,
+                sdk,
+                pending,
+            }
+        });
+    }
+
+    /// Installs to the device switched to, or to none if its port mappings could not be set up.
+    fn update_switch(
+        &mut self,
+        device: Device,
+        sdk: std::result::Result<Option<u32>, AppError>,
+        pending: Option<usize>,
+    ) {
+        let sdk = match sdk {
+            Ok(sdk) => sdk,
+            Err(error) => {
+                self.device = None;
+                self.error(error.to_string());
+                return;
+            }
+        };
+        self.log(format!("Installing to {}", device));
+        self.device_check = Some(Ok(device.to_string()));
+        self.device_sdk = sdk;
+        self.device = Some(device);
+        self.users.clear();
+        self.items.in_progress = pending;
+    }
+
+    /// Reports that no device is ready, telling how to check again once an unauthorized one got
+    /// its USB debugging prompt accepted.
+    fn device_error(&mut self, error: AppError) {
+        match error {
+            AppError::DeviceUnauthorized(_) => {
+                self.error(format!(
+                    "{}, {} checks again",
+                    error,
+                    self.config.keymap.label(Action::CheckDevice)
+                ));
+                self.device_check = Some(Err(error));
+            }
+            error => self.error(error.to_string()),
+        }
+    }
+
+    /// Runs `call`, which talks to ADB and blocks until the device answers, in the background
+    /// and hands the event it returns to `update_task`.
+    fn spawn_adb(&self, call: impl FnOnce() -> TaskEvent + Send + 'static) {
+        let events = self.tasks_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = events.send(call());
+        });
+    }
+
+    /// Fetches the releases again in the background.
+    fn refresh(&mut self) {
+        if self.refreshing {
+            return;
+        }
+        self.refreshing = true;
+        let githubs: Vec<GithubClient> = self
+            .sources
+            .iter()
+            .map(|source| source.github.clone())
+            .collect();
+        let lazy = self.config.lazy;
+        let events = self.tasks_tx.clone();
+        tokio::spawn(async move {
+            let (page_tx, _) = mpsc::unbounded_channel();
+            let result = fetch_sources(&githubs, lazy, page_tx).await;
+            let _ = events.send(TaskEvent::Refreshed(result));
+        });
+    }
+
+    /// Lists the fetched releases, keeping the status of the known ones.
+    fn update_refresh(&mut self, result: std::result::Result<FetchedSources, AppError>) {
+        match result {
+            Ok((releases, more)) => {
+                for (source, more) in self.sources.iter_mut().zip(more) {
+                    source.more = more;
+                }
+                let selected = self
+                    .items
+                    .selected()
+                    .map(|i| (self.items.items[i].source, self.items.items[i].id));
+                let items = releases
+                    .iter()
+                    .map(|(source, release)| {
+                        let mut item =
+                            ReleaseItem::new(*source, release, &self.config.asset_patterns);
+                        if let Some(known) = self
+                            .items
+                            .items
+                            .iter()
+                            .find(|i| i.source == item.source && i.id == item.id)
+                        {
+                            item.status = known.status;
+                            item.pinned = known.pinned;
+                            let picked = known.asset.as_ref().filter(|_| known.asset_picked);
+                            if let Some(asset) = picked.and_then(|picked| {
+                                item.assets.iter().find(|asset| asset.id == picked.id)
+                            }) {
+                                item.asset_id = asset.id;
+                                item.asset = Some(asset.clone());
+                                item.asset_picked = true;
+                            }
+                        }
+                        item
+                    })
+                    .collect();
+                self.items.items = items;
+                if let Some(installing) = &self.installing {
+                    self.items.in_progress = self.items.items.iter().position(|item| {
+                        item.source == installing.source && item.id == installing.id
+                    });
+                }
+                self.summary = summarize(&self.items.items);
+                self.items.diff_base = None;
+                self.items.apply_filter();
+                if let Some((source, id)) = selected {
+                    self.items.select_release(source, id);
+                }
+                self.log(format!("Fetched {} releases", releases.len()));
+            }
+            Err(error) if error.is_unauthorized() => self.ask_token(Retry::Refresh),
+            Err(error) => self.error(format!("Could not fetch releases! {}", error)),
+        }
+    }
+
+    /// Polls every source for new releases in the background.
+    fn start_watching(&mut self) {
+        for (index, source) in self.sources.iter().enumerate() {
+            let known = self
+                .items
+                .items
+                .iter()
+                .filter(|item| item.source == index)
+                .map(|item| item.id)
+                .collect();
+            watch::spawn(
+                index,
+                source.github.clone(),
+                self.config.poll_interval,
+                known,
+                self.watch_tx.clone(),
+            );
+        }
+        self.log(format!(
+            "Watching for new releases every {}s",
+            self.config.poll_interval.as_secs()
+        ));
+    }
+
+    /// Lists the releases found by the watcher and installs the newest one with `--auto-install`.
+    fn update_watch(&mut self, update: std::result::Result<NewReleases, AppError>) {
+        let new = match update {
+            Ok(new) => new,
+            Err(error) => {
+                self.warn(format!("Could not check for new releases! {}", error));
+                return;
+            }
+        };
+
+        self.refresh();
+        let tags: Vec<&str> = new.releases.iter().map(|(_, tag)| tag.as_str()).collect();
+        self.log(format!("New releases: {}", tags.join(", ")));
+        for (id, _) in &new.releases {
+            self.new_releases.insert((new.source, *id));
+        }
+
+        if !self.config.auto_install || self.items.in_progress.is_some() {
+            return;
+        }
+        if let Some((newest, tag_name)) = new.releases.first() {
+            self.auto_install = Some((new.source, *newest, tag_name.clone()));
+        }
+    }
+
+    /// Installs the newest release found by the watcher once it is listed with its assets.
+    fn auto_install_newest(&mut self) {
+        if self.refreshing || self.items.in_progress.is_some() {
+            return;
+        }
+        let Some((source, id, tag_name)) = self.auto_install.clone() else {
+            return;
+        };
+        let Some(index) = self
+            .items
+            .items
+            .iter()
+            .position(|item| item.source == source && item.id == id)
+        else {
+            self.auto_install = None;
+            return;
+        };
+        match self.items.items[index].details {
+            Details::Pending => self.load_release(index),
+            Details::Loading => {}
+            Details::Failed(_) => self.auto_install = None,
+            Details::Complete => {
+                self.auto_install = None;
+                self.log(format!("Auto-installing {}", tag_name));
+                self.items.items[index].status = Status::Installed;
+                self.items.in_progress = Some(index);
+            }
+        }
+    }
+
+    /// Lists the third-party apps installed on the device.
+    fn load_packages(&mut self) {
+        let serial = match self.device() {
+            Ok(device) => device.serial,
+            Err(error) => {
+                self.device_error(error);
+                return;
+            }
+        };
+
+        self.spawn_adb(move || TaskEvent::Packages(adb::installed_packages(Some(&serial))));
+    }
+
+    /// Lists the apps found on the device, selecting the first.
+    fn update_packages(&mut self, result: io::Result<Vec<Package>>) {
+        match result {
+            Ok(packages) => {
+                self.log(format!("Found {} installed apps", packages.len()));
+                self.packages = packages;
+                self.packages_state
+                    .select((!self.packages.is_empty()).then_some(0));
+            }
+            Err(error) => self.error(format!("Could not list installed apps! {}", error)),
+        }
+    }
+
+    fn next_package(&mut self) {
+        if self.packages.is_empty() {
+            return;
+        }
+        let i = self
+            .packages_state
+            .selected()
+            .map_or(0, |i| (i + 1) % self.packages.len());
+        self.packages_state.select(Some(i));
+        self.needs_redraw = true;
+    }
+
+    fn previous_package(&mut self) {
+        if self.packages.is_empty() {
+            return;
+        }
+        let i = self
+            .packages_state
+            .selected()
+            .map_or(0, |i| i.checked_sub(1).unwrap_or(self.packages.len() - 1));
+        self.packages_state.select(Some(i));
+        self.needs_redraw = true;
+    }
+
+    /// Selects the release whose tag contains the version name of the selected app.
+    fn show_matching_release(&mut self) {
+        let Some(package) = self
+            .packages_state
+            .selected()
+            .and_then(|i| self.packages.get(i))
+        else {
+            return;
+        };
+        let package = package.name.clone();
+        let serial = self.device.as_ref().map(|d| d.serial.clone());
+        self.spawn_adb(move || TaskEvent::MatchingVersion {
+            version: adb::version_name(serial.as_deref(), &package),
+            package,
+        });
+    }
+
+    /// Selects the release whose tag contains `version`, the version name of `name`.
+    fn select_matching_release(&mut self, name: String, version: io::Result<Option<String>>) {
+        let version = match version {
+            Ok(Some(version)) => version,
+            Ok(None) => {
+                self.warn(format!("{} has no version name", name));
+                return;
+            }
+            Err(error) => {
+                self.error(format!("Could not read the version of {}! {}", name, error));
+                return;
+            }
+        };
+
+        let version = version.trim_start_matches('v');
+        self.items.filter.clear();
+        self.items.apply_filter();
+        match self
+            .items
+            .visible
+            .iter()
+            .position(|m| self.items.items[m.index].tag_name.contains(version))
+        {
+            Some(position) => {
+                self.items.state.select(Some(position));
+                self.screen = Screen::Releases;
+                self.needs_redraw = true;
+            }
+            None => self.warn(format!("No release matches {} {}", name, version)),
+        }
+    }
+
+    /// Status of the release as recorded, or whether it is the one on the device.
+    fn shown_status(&self, item: &ReleaseItem) -> Status {
+        match (self.status_source, &self.on_device) {
+            (StatusSource::Recorded, _) => item.status,
+            (StatusSource::Device, Some((source, id)))
+                if item.source == *source && item.id == *id =>
+            {
+                Status::Installed
+            }
+            (StatusSource::Device, _) => Status::Open,
+        }
+    }
+
+    /// Switches the statuses between the recorded ones and what is on the device right now.
+    fn toggle_status_source(&mut self) {
+        if self.status_source == StatusSource::Device {
+            self.status_source = StatusSource::Recorded;
+            self.needs_redraw = true;
+            return;
+        }
+        // the package of a release is only known once one of its APKs was installed
+        let Some(package) = self.installed_package.clone() else {
+            self.warn("Install a release first, its package is queried on the device".to_string());
+            return;
+        };
+        let serial = match self.device() {
+            Ok(device) => device.serial,
+            Err(error) => {
+                self.device_error(error);
+                return;
+            }
+        };
+
+        self.spawn_adb(move || TaskEvent::VersionOnDevice {
+            version: adb::version_name(Some(&serial), &package),
+            package,
+        });
+    }
+
+    /// Shows the statuses of what is on the device, the release whose tag contains `version`.
+    fn update_on_device(&mut self, package: String, version: io::Result<Option<String>>) {
+        self.on_device = match version {
+            Ok(Some(version)) => {
+                let version = version.trim_start_matches('v');
+                // the newest of releases sharing a tag
+                let found = self
+                    .items
+                    .items
+                    .iter()
+                    .find(|item| item.tag_name.contains(version))
+                    .map(|item| (item.source, item.id, item.tag_name.clone()));
+                match found {
+                    Some((source, id, tag_name)) => {
+                        self.log(format!("{} {} is on the device", package, tag_name));
+                        Some((source, id))
+                    }
+                    None => {
+                        self.warn(format!("No release matches {} {}", package, version));
+                        None
+                    }
+                }
+            }
+            Ok(None) => {
+                self.log(format!("{} is not installed on the device", package));
+                None
+            }
+            Err(error) => {
+                self.error(format!(
+                    "Could not read the version of {}! {}",
+                    package, error
+                ));
+                return;
+            }
+        };
+        self.status_source = StatusSource::Device;
+        self.needs_redraw = true;
+    }
+
+    /// Asks before downloading every asset of the selected release, listing them with their sizes.
+    fn confirm_download_all(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if item.assets.is_empty() {
+            self.warn(format!("{} has no assets.", item.tag_name));
+            return;
+        }
+        self.needs_redraw = true;
+        self.confirm = Some(Confirmation::DownloadAll {
+            index: i,
+            tag_name: item.tag_name.clone(),
+            assets: item
+                .assets
+                .iter()
+                .map(|asset| (asset.name.clone(), asset.size))
+                .collect(),
+        });
+    }
+
+    /// Downloads every asset of the release at `index` into the download directory.
+    fn download_all(&mut self, index: usize) {
+        let assets = self.items.items[index].assets.clone();
+        self.download_assets(index, assets);
+    }
+
+    /// Downloads the assets of the release at `index` into the download directory.
+    fn download_assets(&mut self, index: usize, assets: Vec<Asset>) {
+        let item = &self.items.items[index];
+        let downloads = assets
+            .iter()
+            .map(|asset| (asset.clone(), self.download_path(item, asset)))
+            .collect();
+
+        self.transfers
+            .retain(|t| !assets.iter().any(|a| a.id == t.asset_id));
+        self.transfers.extend(assets.iter().map(|asset| Transfer {
+            asset_id: asset.id,
+            name: asset.display_name().to_string(),
+            state: TransferState::Queued,
+        }));
+        let github = &self.sources[item.source].github;
+        transfer::download_all(github, downloads, self.updates_tx.clone());
+    }
+
+    /// Downloads the APK of the selected release in the background and copies an `adb install`
+    /// command for it.
+    fn copy_install_command(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        let Some(asset) = &item.asset else {
+            self.warn("No APK asset found in the selected release.".to_string());
+            return;
+        };
+
+        let path = self.download_path(item, asset);
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let asset = asset.clone();
+        let github = self.sources[item.source].github.clone();
+
+        let mut command = "adb".to_string();
+        if let Ok(device) = self.device() {
+            command += &format!(" -s {}", device.serial);
+        }
+        command += &format!(
+            " install -r --user {} {}",
+            self.config.user_id,
+            path.display()
+        );
+
+        let events = self.tasks_tx.clone();
+        tokio::spawn(async move {
+            if let Err(error) = tokio::fs::create_dir_all(&dir).await {
+                let message = format!("Could not create {}! {}", dir.display(), error);
+                let _ = events.send(TaskEvent::Message(Severity::Error, message));
+                return;
+            }
+            let result = github
+                .download_asset(&asset, &path.to_string_lossy(), |_, _| {})
+                .await
+                .map(|_| command);
+            let _ = events.send(TaskEvent::InstallCommand(result));
+        });
+    }
+
+    /// Copies the Markdown notes of the selected release.
+    fn copy_notes(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if item.body.trim().is_empty() {
+            self.warn(format!("{} has no release notes.", item.tag_name));
+            return;
+        }
+        let message = format!(
+            "Copied the notes of {} ({} lines)",
+            item.tag_name,
+            item.body.lines().count()
+        );
+        match clipboard::copy(&item.body) {
+            Ok(()) => self.log(message),
+            Err(error) => self.warn(format!("Could not copy the notes! {}", error)),
+        }
+    }
+
+    /// Downloads the APK of the selected release and copies it to the device without installing it,
+    /// in the background.
+    fn push_only(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let item = &self.items.items[i];
+        if !matches!(item.details, Details::Complete) {
+            self.warn(format!(
+                "The assets of {} are not loaded yet.",
+                item.tag_name
+            ));
+            return;
+        }
+        let Some(asset) = item.asset.clone() else {
+            self.warn("No APK asset found in the selected release.".to_string());
+            return;
+        };
+        let loaded = match &item.checksums {
+            Checksums::Loaded(hashes) => Some(hashes.clone()),
+            _ => None,
+        };
+        let assets = item.assets.clone();
+        let source = &self.sources[item.source];
+        let github = source.github.clone();
+        let config = self.config.for_repo(&source.repo);
+        let device = match self.device() {
+            Ok(device) => device,
+            Err(error) => {
+                self.device_error(error);
+                return;
+            }
+        };
+
+        let events = self.tasks_tx.clone();
+        tokio::spawn(async move {
+            let result = async {
+                // not loaded yet if the release was not selected for long
+                let hashes = match loaded {
+                    Some(hashes) => hashes,
+                    None => checksums::load(&github, &assets).await?,
+                };
+                install::push_asset(
+                    &github,
+                    &config,
+                    &device.serial,
+                    &asset,
+                    hashes.get(&asset.name).map(String::as_str),
+                    |message| {
+                        let _ = events.send(TaskEvent::Message(Severity::Warn, message));
+                    },
+                )
+                .await
+            }
+            .await;
+            let _ = events.send(TaskEvent::Pushed {
+                asset: asset.name,
+                device: device.to_string(),
+                result,
+            });
+        });
+    }
+
+    /// Switches to the next sort, keeping the selected release selected, and saves it.
+    fn cycle_sort(&mut self) {
+        let selected = self
+            .items
+            .selected()
+            .map(|i| (self.items.items[i].source, self.items.items[i].id));
+        self.items.sort = self.items.sort.next();
+        self.items.apply_filter();
+        if let Some((source, id)) = selected {
+            self.items.select_release(source, id);
+        }
+        self.log(format!("Sorted by {}", self.items.sort));
+        if let Err(error) = self.items.sort.save() {
+            self.error(format!("Could not save the sort! {}", error));
+        }
+    }
+
+    /// Pins the selected release to the top of the list, or unpins it, and saves the pins.
+    fn toggle_pin(&mut self) {
+        let Some(i) = self.items.selected() else {
+            return;
+        };
+        let (source, id) = (self.items.items[i].source, self.items.items[i].id);
+        let key = pin_key(&self.config, &self.sources[source].repo);
+        let pinned = self.pins.toggle(&key, id);
+        self.items.items[i].pinned = pinned;
+        let tag_name = self.items.items[i].tag_name.clone();
+
+        self.items.apply_filter();
+        self.items.select_release(source, id);
+        self.log(format!(
+            "{} {}",
+            if pinned { "Pinned" } else { "Unpinned" },
+            tag_name
+        ));
+        if let Err(error) = self.pins.save() {
+            self.error(format!("Could not save the pins! {}", error));
+        }
+    }
+
+    /// Writes the installs of the session to `--report`, or a new file in the downloads.
+    fn export_report(&mut self) {
+        if self.installs.is_empty() {
+            self.warn("Nothing installed yet".to_string());
+            return;
+        }
+        let path = self
+            .config
+            .report
+            .clone()
+            .unwrap_or_else(report::default_path);
+        match report::write(&path, &self.installs) {
+            Ok(()) => self.log(format!(
+                "Exported {} installs to {}",
+                self.installs.len(),
+                path.display()
+            )),
+            Err(error) => self.error(format!("Could not export the installs! {}", error)),
+        }
+    }
+
+    /// Where an asset of a release is downloaded to.
+    fn download_path(&self, item: &ReleaseItem, asset: &Asset) -> PathBuf {
+        let repo = &self.sources[item.source].repo;
+        match &self.config.output_template {
+            Some(template) => template.expand(&repo.owner, &repo.repo, &item.tag_name, &asset.name),
+            None => dirs::download_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(path_safe(&repo.repo))
+                .join(path_safe(&item.tag_name))
+                .join(path_safe(&asset.name)),
+        }
+    }
+
+    /// The cached APKs of a source.
+    fn cache(&self, source: usize) -> Cache {
+        Cache::new(&self.config.for_repo(&self.sources[source].repo))
+    }
+
+    /// Fetches the selected release on its own if only part of it is known.
+    fn load_details(&mut self) {
+        if let Some(index) = self.items.selected() {
+            self.load_release(index);
+        }
+    }
+
+    /// Fetches the release at `index` on its own if only part of it is known.
+    fn load_release(&mut self, index: usize) {
+        let item = &mut self.items.items[index];
+        if !matches!(item.details, Details::Pending) {
+            return;
+        }
+
+        item.details = Details::Loading;
+        let source = item.source;
+        let github = self.sources[source].github.clone();
+        let id = item.id;
+        let details = self.details_tx.clone();
+        tokio::spawn(async move {
+            let result = github.fetch_release(id).await;
+            let _ = details.send((source, id, result));
+        });
+    }
+
+    /// Fetches the checksum manifest of the selected release once its assets are known.
+    fn load_checksums(&mut self) {
+        let Some(item) = self.items.selected().map(|i| &mut self.items.items[i]) else {
+            return;
+        };
+        if !matches!(item.checksums, Checksums::Pending)
+            || !matches!(item.details, Details::Complete)
+        {
+            return;
+        }
+        if checksums::manifest(&item.assets).is_none() {
+            item.checksums = Checksums::Loaded(HashMap::new());
+            return;
+        }
+
+        item.checksums = Checksums::Loading;
+        let (source, id) = (item.source, item.id);
+        let github = self.sources[source].github.clone();
+        let assets = item.assets.clone();
+        let checksums = self.checksums_tx.clone();
+        tokio::spawn(async move {
+            let result = checksums::load(&github, &assets).await;
+            let _ = checksums.send((source, id, result));
+        });
+    }
+
+    fn update_checksums(
+        &mut self,
+        source: usize,
+        id: i64,
+        result: std::result::Result<HashMap<String, String>, AppError>,
+    ) {
+        if let Some(item) = self
+            .items
+            .items
+            .iter_mut()
+            .find(|item| item.source == source && item.id == id)
+        {
+            item.checksums = match result {
+                Ok(hashes) => Checksums::Loaded(hashes),
+                Err(error) => Checksums::Failed(error.to_string()),
+            };
+        }
+    }
+
+    /// Fetches the following releases of the sources stopped by the limit once the last listed
+    /// release is selected.
+    fn load_more(&mut self) {
+        let last = self.items.visible.len().checked_sub(1);
+        if self.loading_more > 0 || last.is_none() || self.items.state.selected() != last {
+            return;
+        }
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            let Some(continuation) = source.more.take() else {
+                continue;
+            };
+            self.loading_more += 1;
+            let github = source.github.clone();
+            let more = self.more_tx.clone();
+            tokio::spawn(async move {
+                let result = github.fetch_from(continuation, |_, _| {}).await;
+                let _ = more.send((index, result));
+            });
+        }
+    }
+
+    /// Adds releases fetched beyond the limit below the listed ones, keeping the selection.
+    fn update_more(&mut self, source: usize, result: std::result::Result<MoreReleases, AppError>) {
+        self.loading_more -= 1;
+        let (releases, more) = match result {
+            Ok(fetched) => fetched,
+            Err(error) => {
+                self.error(format!(
+                    "Could not fetch more releases of {}! {}",
+                    self.sources[source].repo, error
+                ));
+                return;
+            }
+        };
+        self.sources[source].more = more;
+        let selected = self
+            .items
+            .selected()
+            .map(|i| (self.items.items[i].source, self.items.items[i].id));
+        let key = pin_key(&self.config, &self.sources[source].repo);
+        for release in &releases {
+            // already listed if the list was refreshed meanwhile
+            if self
+                .items
+                .items
+                .iter()
+                .any(|item| item.source == source && item.id == release.id)
+            {
+                continue;
+            }
+            let mut item = ReleaseItem::new(source, release, &self.config.asset_patterns);
+            item.pinned = self.pins.contains(&key, item.id);
+            self.items.items.push(item);
+        }
+        self.summary = summarize(&self.items.items);
+        self.items.apply_filter();
+        if let Some((source, id)) = selected {
+            self.items.select_release(source, id);
+        }
+        self.log(format!(
+            "Fetched {} more releases of {}",
+            releases.len(),
+            self.sources[source].repo
+        ));
+    }
+
+    /// Replaces a release with the one fetched on its own, keeping its status.
+    fn update_details(
+        &mut self,
+        source: usize,
+        id: i64,
+        result: std::result::Result<Release, AppError>,
+    ) {
+        let Some(item) = self
+            .items
+            .items
+            .iter_mut()
+            .find(|item| item.source == source && item.id == id)
+        else {
+            return;
+        };
+        let tag_name = item.tag_name.clone();
+
+        match result {
+            Ok(release) => {
+                let (status, pinned) = (item.status, item.pinned);
+                *item = ReleaseItem::new(source, &release, &self.config.asset_patterns);
+                item.status = status;
+                item.pinned = pinned;
+                self.summary = summarize(&self.items.items);
+            }
+            Err(error) if error.is_unauthorized() => {
+                item.details = Details::Failed(error.to_string());
+                self.ask_token(Retry::Details { source, id });
+            }
+            Err(error) => {
+                item.details = Details::Failed(error.to_string());
+                self.error(format!("Could not load {}! {}", tag_name, error));
+            }
+        }
+    }
+
+    fn update_broadcast(&mut self, update: InstallUpdate) {
+        let Some(install) = self.broadcast.get_mut(update.device) else {
+            return;
+        };
+        install.state = update.state;
+        if let (InstallState::Installed | InstallState::Failed(_), Some((repo, asset))) =
+            (&install.state, &self.broadcast_release)
+        {
+            self.installs.push(InstallRecord {
+                time: Utc::now(),
+                repo: repo.clone(),
+                tag: self.broadcast_tag.clone().unwrap_or_default(),
+                asset: asset.clone(),
+                sha256: update.sha256,
+                serial: install.device.serial.clone(),
+                result: match &install.state {
+                    InstallState::Failed(error) => error.clone(),
+                    _ => "installed".to_string(),
+                },
+            });
+        }
+        if self
+            .broadcast
+            .iter()
+            .all(|install| install.state.is_finished())
+        {
+            if let Some(tag_name) = self.broadcast_tag.take() {
+                let (installed, failed) = self.broadcast_counts();
+                let message = format!(
+                    "Installed {} to {} devices, {} failed",
+                    tag_name, installed, failed
+                );
+                if failed > 0 {
+                    self.warn(message);
+                } else {
+                    self.log(message);
+                }
+            }
+        }
+    }
+
+    /// Number of devices of the broadcast install that succeeded and that failed.
+    fn broadcast_counts(&self) -> (usize, usize) {
+        let count = |f: fn(&InstallState) -> bool| {
+            self.broadcast
+                .iter()
+                .filter(|install| f(&install.state))
+                .count()
+        };
+        (
+            count(|state| matches!(state, InstallState::Installed)),
+            count(|state| matches!(state, InstallState::Failed(_))),
+        )
+    }
+
+    fn update_transfer(&mut self, update: TransferUpdate) {
+        if let Some(transfer) = self
+            .transfers
+            .iter_mut()
+            .find(|t| t.asset_id == update.asset_id)
+        {
+            transfer.state = update.state;
+        }
+    }
+
+    /// Switches between the compact and detailed list and remembers the choice.
+    fn toggle_view(&mut self) {
+        self.config.view = self.config.view.toggled();
+        self.needs_redraw = true;
+        if let Err(error) = config::save_setting("view", self.config.view.as_str().into()) {
+            self.warn(format!("Could not save view mode! {}", error));
+        }
+    }
+
+    /// Shows or hides the actions, remembering it for the next start.
+    fn toggle_actions(&mut self) {
+        self.actions_hidden = !self.actions_hidden;
+        self.needs_redraw = true;
+        if let Err(error) = config::save_setting("hide_actions", self.actions_hidden.into()) {
+            self.warn(format!(
+                "Could not save whether actions are hidden! {}",
+                error
+            ));
+        }
+    }
+
+    /// Switches the target user to the next one present on the device.
+    fn next_user(&mut self) {
+        let serial = match self.device() {
+            Ok(device) => device.serial,
+            Err(error) => {
+                self.device_error(error);
+                return;
             }
+        };
 
-            // TODO: install selected apk
-            if let Some(index) = self.items.in_progress {
-                if self.items.items[index].asset_id == -1 {
-                    println!("No APK asset found in the selected release.");
-                } else {
-                    let asset_id = self.items.items[index].asset_id;
+        if !self.users.is_empty() {
+            self.cycle_user();
+            return;
+        }
+        self.spawn_adb(move || {
+            let users = adb::ensure_server().and_then(|()| Ok(adb::list_users(Some(&serial))?));
+            TaskEvent::Users(users)
+        });
+    }
+
+    /// Installs for the user listed after the current one.
+    fn cycle_user(&mut self) {
+        let position = self
+            .users
+            .iter()
+            .position(|u| u.id == self.config.user_id)
+            .map_or(0, |p| (p + 1) % self.users.len());
+        if let Some(user) = self.users.get(position) {
+            self.config.user_id = user.id;
+            let message = format!("Installing for user {} ({})", user.id, user.name);
+            self.log(message);
+        }
+    }
 
-                    let apk_path = "/tmp/app.apk";
+    fn log(&mut self, message: String) {
+        self.log.push(Severity::Info, message);
+        self.needs_redraw = true;
+    }
 
-                    let token = match env::var_os("GH_ACCESS_TOKEN") {
-                        Some(v) => v.into_string().unwrap(),
-                        None => panic!("$GH_ACCESS_TOKEN is not set"),
-                    };
-                    let owner = env::var_os("GH_OWNER").unwrap().into_string().unwrap();
-                    let repo = env::var_os("GH_REPO").unwrap().into_string().unwrap();
-
-                    let download_result =
-                        download_asset(&owner, &repo, &token, asset_id, apk_path).await;
-
-                    match download_result {
-                        Ok(_) => {
-                            // create an ADB connection to the device
-                            let mut connection =
-                                AdbTcpConnection::new(Ipv4Addr::from([127, 0, 0, 1]), 5037)
-                                    .unwrap();
-
-                            let mut input = File::open(Path::new(&apk_path)).unwrap();
-                            let send_result = connection.send(
-                                None::<String>,
-                                &mut input,
-                                "/data/local/tmp/app.apk",
-                            );
-
-                            match send_result {
-                                Ok(_) => {
-                                    // TODO: handle result
-                                    let install_result = connection.shell_command(
-                                        &None,
-                                        vec!["pm", "install", "-r", "/data/local/tmp/app.apk"],
-                                    );
-
-                                    match install_result {
-                                        Ok(_) => {
-                                            //
-                                            self.items.in_progress = None;
-                                        }
-                                        Err(error) => {
-                                            println!("Could not install apk on device! {}", error);
-                                            self.items.in_progress = None;
-                                        }
-                                    }
-                                }
-                                Err(error) => {
-                                    println!("Could not send apk to device! {}", error)
-                                }
-                            }
-                        }
-                        Err(error) => println!("Could not download apk from github! {}", error),
-                    }
-                };
+    fn warn(&mut self, message: String) {
+        self.log.push(Severity::Warn, message);
+        self.needs_redraw = true;
+    }
+
+    fn error(&mut self, message: String) {
+        self.log.push(Severity::Error, message);
+        self.needs_redraw = true;
+    }
+
+    /// Short form of the install progress shown in the row of the release being installed.
+    fn row_progress(&self) -> String {
+        match &self.download {
+            Some((Phase::Installing, _)) => "installing".to_string(),
+            Some((Phase::Downloading, download))
+                if download.done > 0 && Some(download.done) == download.total =>
+            {
+                "checking".to_string()
+            }
+            Some((phase, download)) if download.done > 0 && download.total.is_some() => {
+                format!(
+                    "{} {:.0}%",
+                    phase.to_string().to_lowercase(),
+                    download.ratio() * 100.0
+                )
             }
+            Some((phase, download)) if download.done > 0 => format!(
+                "{} {}",
+                phase.to_string().to_lowercase(),
+                format_size(download.done)
+            ),
+            Some((phase, _)) => phase.to_string().to_lowercase(),
+            None => "downloading".to_string(),
         }
     }
 
+    /// Whether a spinner is shown, which needs a redraw on every tick.
+    fn animating(&self) -> bool {
+        self.installing.is_some()
+            || self.broadcast_tag.is_some()
+            || self
+                .items
+                .items
+                .iter()
+                .any(|item| matches!(item.details, Details::Loading))
+    }
+
     fn draw(&mut self, terminal: &mut Terminal<impl Backend>) -> io::Result<()> {
-        terminal.draw(|f| f.render_widget(self, f.size()))?;
+        terminal.draw(|f| f.render_widget(&mut *self, f.size()))?;
+        self.needs_redraw = false;
         Ok(())
     }
 }
 
-impl<'a> App<'a> {
-    fn new(releases: &'a [Release]) -> Self {
+impl App {
+    fn new(
+        config: Config,
+        sources: Vec<Source>,
+        releases: &[(usize, Release)],
+        notices_rx: UnboundedReceiver<String>,
+    ) -> Self {
+        let (pins, pins_error) = match Pins::load() {
+            Ok(pins) => (pins, None),
+            Err(error) => (Pins::default(), Some(error)),
+        };
+        let mut items = StatefulList {
+            state: ListState::default(),
+            items: releases
+                .iter()
+                .map(|(source, release)| {
+                    let mut item = ReleaseItem::new(*source, release, &config.asset_patterns);
+                    item.pinned = pins.contains(&pin_key(&config, &sources[*source].repo), item.id);
+                    item
+                })
+                .collect(),
+            filter: String::new(),
+            installable_only: false,
+            sort: config.sort,
+            diff_base: None,
+            visible: Vec::new(),
+            last_selected: None,
+            in_progress: None,
+        };
+        items.apply_filter();
+
+        let mut log = Log::new();
+        if let Some(error) = pins_error {
+            log.push(
+                Severity::Warn,
+                format!("Could not read the pins! {}", error),
+            );
+        }
+        if config.token.is_none() {
+            log.push(
+                Severity::Warn,
+                "$GH_ACCESS_TOKEN is not set, accessing GitHub anonymously (60 requests per hour)"
+                    .to_string(),
+            );
+        }
+
+        if config.auto_prune && config.cache_limits.is_set() {
+            let pruned: io::Result<Vec<u64>> = sources
+                .iter()
+                .map(|source| {
+                    Cache::new(&config.for_repo(&source.repo)).prune(&config.cache_limits)
+                })
+                .collect();
+            match pruned.map(|freed| freed.iter().sum()) {
+                Ok(0) => {}
+                Ok(freed) => log.push(
+                    Severity::Info,
+                    format!("Pruned the cache, freed {}", format_size(freed)),
+                ),
+                Err(error) => log.push(
+                    Severity::Warn,
+                    format!("Could not prune the cache! {}", error),
+                ),
+            }
+        }
+
+        let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+        let (broadcast_tx, broadcast_rx) = mpsc::unbounded_channel();
+        let (details_tx, details_rx) = mpsc::unbounded_channel();
+        let (more_tx, more_rx) = mpsc::unbounded_channel();
+        let (tasks_tx, tasks_rx) = mpsc::unbounded_channel();
+        let (watch_tx, watch_rx) = mpsc::unbounded_channel();
+        let (checksums_tx, checksums_rx) = mpsc::unbounded_channel();
+
         Self {
-            items: StatefulList {
-                state: ListState::default(),
-                items: releases.iter().map(ReleaseItem::from).collect(),
-                last_selected: None,
-                in_progress: None,
-            },
+            summary: summarize(&items.items),
+            actions_hidden: config.hide_actions,
+            config,
+            sources,
+            items,
+            screen: Screen::Releases,
+            packages: Vec::new(),
+            packages_state: ListState::default(),
+            device: None,
+            users: Vec::new(),
+            log,
+            history: Vec::new(),
+            searching: false,
+            notes_search: None,
+            tag_prompt: None,
+            confirm: None,
+            json_scroll: None,
+            asset_picker: None,
+            device_picker: None,
+            replace: Replace::default(),
+            transfers: Vec::new(),
+            device_check: None,
+            device_sdk: None,
+            queue: VecDeque::new(),
+            queue_total: 0,
+            installed_package: None,
+            status_source: StatusSource::Recorded,
+            on_device: None,
+            logcat: None,
+            download: None,
+            installing: None,
+            refreshing: false,
+            auto_install: None,
+            tasks_tx,
+            tasks_rx,
+            tick: 0,
+            needs_redraw: true,
+            updates_tx,
+            updates_rx,
+            broadcast: Vec::new(),
+            broadcast_tag: None,
+            broadcast_release: None,
+            broadcast_tx,
+            broadcast_rx,
+            details_tx,
+            details_rx,
+            more_tx,
+            more_rx,
+            loading_more: 0,
+            checksums_tx,
+            checksums_rx,
+            token_prompt: None,
+            info_expanded: false,
+            pins,
+            installs: Vec::new(),
+            notices_rx,
+            new_releases: HashSet::new(),
+            watch_tx,
+            watch_rx,
         }
     }
     /// Changes the status of the selected list item
     fn flip_status(&mut self) {
-        if let Some(i) = self.items.state.selected() {
-            self.items.in_progress = Some(i);
-            self.items.items[i].status = match self.items.items[i].status {
-                Status::Installed => Status::Open,
-                Status::Open => Status::Installed,
+        if let Some(i) = self.items.selected() {
+            self.flip_status_of(i);
+        }
+    }
+
+    /// Changes the status of the release at `i`, asking first before installing a stale one.
+    fn flip_status_of(&mut self, i: usize) {
+        let item = &self.items.items[i];
+        if matches!(item.status, Status::Open) {
+            if let Some(stale) = self.stale(i) {
+                self.needs_redraw = true;
+                self.confirm = Some(Confirmation::Stale {
+                    index: i,
+                    tag_name: item.tag_name.clone(),
+                    stale,
+                });
+                return;
             }
         }
+        self.flip_status_at(i);
+    }
+
+    fn flip_status_at(&mut self, i: usize) {
+        let item = &mut self.items.items[i];
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(StatusChange {
+            source: item.source,
+            id: item.id,
+            tag_name: item.tag_name.clone(),
+            previous: item.status,
+        });
+
+        self.items.in_progress = Some(i);
+        item.status = match item.status {
+            Status::Installed => Status::Open,
+            Status::Open => Status::Installed,
+        };
+        self.needs_redraw = true;
+    }
+
+    /// How old the release at `index` is if it was published longer than `stale_after` ago and
+    /// its repository has newer releases.
+    fn stale(&self, index: usize) -> Option<Stale> {
+        let threshold = chrono::Duration::from_std(self.config.stale_after?).ok()?;
+        let item = &self.items.items[index];
+        let age = Utc::now() - item.published_at?;
+        if age <= threshold {
+            return None;
+        }
+        let newest = self
+            .items
+            .items
+            .iter()
+            .filter(|other| other.source == item.source)
+            .filter_map(|other| Some((other.published_at?, other)))
+            .max_by_key(|(published_at, _)| *published_at)
+            .filter(|(published_at, _)| Some(*published_at) > item.published_at)?;
+        Some(Stale {
+            age,
+            newest: newest.1.tag_name.clone(),
+        })
+    }
+
+    /// Reverts the most recent status change whose install did not complete.
+    ///
+    /// Not while an install is running, it would complete with the status reverted.
+    fn undo(&mut self) {
+        if self.installing.is_some() || self.items.in_progress.is_some() {
+            self.warn("Can not undo while installing".to_string());
+            return;
+        }
+        let Some(change) = self.history.pop() else {
+            self.log("Nothing to undo".to_string());
+            return;
+        };
+        let Some(i) = self
+            .items
+            .items
+            .iter()
+            .position(|item| item.source == change.source && item.id == change.id)
+        else {
+            return;
+        };
+
+        self.items.items[i].status = change.previous;
+        if matches!(
+            self.confirm,
+            Some(Confirmation::Reinstall(index) | Confirmation::Downgrade { index, .. }) if index == i
+        ) {
+            self.confirm = None;
+        }
+        self.log(format!(
+            "Reverted {} to {}",
+            change.tag_name, change.previous
+        ));
+    }
+
+    /// Marks the selected release as the base the notes of other releases are compared against.
+    fn toggle_diff_base(&mut self) {
+        let selected = self.items.selected();
+        self.items.diff_base = if self.items.diff_base == selected {
+            None
+        } else {
+            selected
+        };
+        self.needs_redraw = true;
     }
 
     fn go_top(&mut self) {
-        self.items.state.select(Some(0));
+        if !self.items.visible.is_empty() {
+            self.items.state.select(Some(0));
+            self.needs_redraw = true;
+        }
     }
 
     fn go_bottom(&mut self) {
-        self.items.state.select(Some(self.items.items.len() - 1));
+        self.items
+            .state
+            .select(self.items.visible.len().checked_sub(1));
+        self.needs_redraw = true;
     }
 }
 
-impl StatefulList<'_> {
+impl StatefulList {
+    /// Index into `items` of the selected release.
+    fn selected(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.visible.get(i))
+            .map(|m| m.index)
+    }
+
+    /// Shows the releases whose tag fuzzy-matches the filter, best matches first.
+    fn apply_filter(&mut self) {
+        let matcher = SkimMatcherV2::default();
+        let mut matches: Vec<(i64, Match)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                // whether a release that is not loaded yet has an APK is unknown
+                !self.installable_only
+                    || item.asset_id != -1
+                    || !matches!(item.details, Details::Complete)
+            })
+            .filter_map(|(index, item)| {
+                if self.filter.is_empty() {
+                    return Some((
+                        0,
+                        Match {
+                            index,
+                            positions: Vec::new(),
+                        },
+                    ));
+                }
+                matcher
+                    .fuzzy_indices(&item.tag_name, &self.filter)
+                    .map(|(score, positions)| (score, Match { index, positions }))
+            })
+            .collect();
+        matches.sort_by(|(_, a), (_, b)| self.compare(a.index, b.index));
+        // pinned releases first, stable so the sort applies among equally good matches
+        matches.sort_by_key(|(score, m)| (!self.items[m.index].pinned, Reverse(*score)));
+
+        self.visible = matches.into_iter().map(|(_, m)| m).collect();
+        self.last_selected = None;
+        if self.filter.is_empty() || self.visible.is_empty() {
+            self.state.select(None);
+        } else {
+            self.state.select(Some(0));
+        }
+    }
+
+    /// Orders the releases at the indices by [`StatefulList::sort`].
+    fn compare(&self, a: usize, b: usize) -> std::cmp::Ordering {
+        let (first, second) = (&self.items[a], &self.items[b]);
+        let downloads = |item: &ReleaseItem| -> u64 {
+            item.assets.iter().map(|asset| asset.download_count).sum()
+        };
+        let ordering = match self.sort.key {
+            // the releases are listed newest first
+            SortKey::Created => b.cmp(&a),
+            SortKey::Semver => sort::compare_versions(&first.tag_name, &second.tag_name),
+            SortKey::Published => first.published_at.cmp(&second.published_at),
+            SortKey::Downloads => downloads(first).cmp(&downloads(second)),
+        };
+        // newest first among equals, whichever the direction
+        self.sort.apply(ordering.reverse()).then(a.cmp(&b))
+    }
+
+    /// Selects the release if it is visible.
+    fn select_release(&mut self, source: usize, id: i64) {
+        if let Some(position) = self.visible.iter().position(|m| {
+            let item = &self.items[m.index];
+            item.source == source && item.id == id
+        }) {
+            self.state.select(Some(position));
+        }
+    }
+
     fn next(&mut self) {
+        if self.visible.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.items.len() - 1 {
+                if i >= self.visible.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -336,10 +4616,13 @@ impl StatefulList<'_> {
     }
 
     fn previous(&mut self) {
+        if self.visible.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.items.len() - 1
+                    self.visible.len() - 1
                 } else {
                     i - 1
                 }
@@ -357,20 +4640,814 @@ impl StatefulList<'_> {
     }
 }
 
-impl<'a> From<&'a Release> for ReleaseItem<'a> {
-    fn from(release: &'a github::Release) -> Self {
-        let download_url =
-            if let Some(asset) = release.assets.iter().find(|a| a.name.ends_with(".apk")) {
-                asset.id
-            } else {
-                -1i32
-            };
+impl ReleaseItem {
+    /// Marks installed releases and those without an asset to install.
+    fn status_glyph(&self, tick: usize, status: Status) -> Span<'static> {
+        match &self.details {
+            Details::Loading => {
+                return Span::styled(
+                    format!("{} ", spinner(tick)),
+                    Style::default().fg(Color::Yellow),
+                )
+            }
+            Details::Failed(_) => return Span::styled("✗ ", Style::default().fg(Color::Red)),
+            Details::Complete | Details::Pending => {}
+        }
+        match (self.asset_id, status) {
+            (-1, _) => Span::styled("⊘ ", Style::default().fg(Color::DarkGray)),
+            (_, Status::Installed) => Span::styled("✓ ", Style::default().fg(Color::Green)),
+            (_, Status::Open) => Span::raw("○ "),
+        }
+    }
+
+    fn new(source: usize, release: &Release, asset_patterns: &[String]) -> Self {
+        let asset = resolve_asset(release, asset_patterns).cloned();
+        let download_url = asset.as_ref().map_or(-1i32, |a| a.id);
 
         Self {
-            tag_name: &release.tag_name,
-            body: &release.body,
+            source,
+            id: release.id,
+            tag_name: release.tag_name.clone(),
+            body: sanitize(&release.body),
+            raw: serde_json::to_string_pretty(&release.raw).unwrap_or_default(),
+            published_at: release.published_at,
+            target_commitish: release.target_commitish.clone(),
             asset_id: download_url,
+            asset,
+            asset_picked: false,
+            assets: release.assets.clone(),
             status: Status::Open,
+            pinned: false,
+            checksums: Checksums::Pending,
+            reactions: release.reactions.clone(),
+            sdk: None,
+            details: if release.partial {
+                Details::Pending
+            } else {
+                Details::Complete
+            },
+        }
+    }
+}
+
+fn render_logcat(logcat: &Logcat, area: Rect, buf: &mut Buffer) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = logcat
+        .lines
+        .iter()
+        .skip(logcat.lines.len().saturating_sub(visible))
+        .map(|line| {
+            // `-v time` lines look like `01-02 03:04:05.678 E/Tag( 123): message`
+            let color = match line
+                .split_whitespace()
+                .nth(2)
+                .and_then(|tag| tag.chars().next())
+            {
+                Some('E' | 'F') => Color::Red,
+                Some('W') => Color::Yellow,
+                Some('V' | 'D') => Color::DarkGray,
+                _ => Color::Reset,
+            };
+            Line::styled(line.as_str(), Style::default().fg(color))
+        })
+        .collect();
+
+    let mut title = format!("Logcat {} (pid {})", logcat.package, logcat.pid);
+    if logcat.paused {
+        title += " [paused]";
+    }
+    Clear.render(area, buf);
+    Paragraph::new(lines)
+        .block(Block::new().title(title).borders(Borders::ALL))
+        .render(area, buf);
+}
+
+fn render_token_prompt(input: &str, area: Rect, buf: &mut Buffer) {
+    let popup_area = centered_rect(area, 60, 20);
+    let text = vec![
+        Line::from("GitHub rejected the token, it may have expired."),
+        Line::from("Paste a new one to retry, it is used until the app exits."),
+        Line::from(""),
+        Line::from("•".repeat(input.chars().count())),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Enter", Style::default().fg(Color::LightBlue)),
+            " to retry, ".into(),
+            Span::styled("Esc", Style::default().fg(Color::LightBlue)),
+            " to cancel".into(),
+        ]),
+    ];
+
+    Clear.render(popup_area, buf);
+    Paragraph::new(text)
+        .block(
+            Block::bordered()
+                .title(Title::from("401 Unauthorized").alignment(Alignment::Center))
+                .border_type(BorderType::Rounded),
+        )
+        .centered()
+        .render(popup_area, buf);
+}
+
+fn render_confirm(confirmation: &Confirmation, area: Rect, buf: &mut Buffer) {
+    let (title, question, action) = match confirmation {
+        Confirmation::Reinstall(_) => (
+            "Signature mismatch".to_string(),
+            vec![
+                Line::from("The installed app is signed with a different certificate."),
+                Line::from("Uninstall it (removing its data) and install again?"),
+            ],
+            "uninstall and install",
+        ),
+        Confirmation::Stale {
+            tag_name, stale, ..
+        } => (
+            "Old release".to_string(),
+            vec![
+                stale_line(tag_name, stale),
+                Line::from("Install it anyway?"),
+            ],
+            "install",
+        ),
+        Confirmation::Broadcast {
+            tag_name,
+            asset: (name, size),
+            devices,
+            stale,
+            ..
+        } => {
+            let mut lines = vec![Line::from(format!(
+                "Install {} ({}, {}) to all {} ready devices?",
+                tag_name,
+                name,
+                format_size(*size),
+                devices.len()
+            ))];
+            lines.extend(batch_lines(devices.iter().map(|device| device.to_string())));
+            lines.push(Line::from(format!(
+                "{} to download once",
+                format_size(*size)
+            )));
+            lines.extend(stale.as_ref().map(|stale| stale_line(tag_name, stale)));
+            ("Install to all devices".to_string(), lines, "install")
+        }
+        Confirmation::DownloadAll {
+            tag_name, assets, ..
+        } => {
+            let mut lines = vec![Line::from(format!(
+                "Download all {} assets of {}?",
+                assets.len(),
+                tag_name
+            ))];
+            lines.extend(batch_lines(
+                assets
+                    .iter()
+                    .map(|(name, size)| format!("{}  {}", name, format_size(*size))),
+            ));
+            let total = assets.iter().map(|(_, size)| size).sum();
+            lines.push(Line::from(format!("{} in total", format_size(total))));
+            ("Download all assets".to_string(), lines, "download")
+        }
+        Confirmation::RunQueue { releases, device } => {
+            let mut lines = vec![Line::from(format!(
+                "Install {} queued releases to {}?",
+                releases.len(),
+                device
+            ))];
+            lines.extend(batch_lines(releases.iter().map(
+                |(tag_name, asset)| match asset {
+                    Some((name, size)) => {
+                        format!("{}  {}  {}", tag_name, name, format_size(*size))
+                    }
+                    None => format!("{}  no APK", tag_name),
+                },
+            )));
+            let total = releases
+                .iter()
+                .filter_map(|(_, asset)| asset.as_ref().map(|(_, size)| size))
+                .sum();
+            lines.push(Line::from(format!(
+                "{} to download at most, cached APKs are not downloaded again",
+                format_size(total)
+            )));
+            ("Install the queue".to_string(), lines, "install")
+        }
+        Confirmation::CleanInstall {
+            tag_name, stale, ..
+        } => {
+            let mut lines = vec![
+                Line::from("Uninstall the app, removing its data, if it is installed,"),
+                Line::from(format!("then install {} fresh?", tag_name)),
+            ];
+            lines.extend(stale.as_ref().map(|stale| stale_line(tag_name, stale)));
+            ("Clean install".to_string(), lines, "uninstall and install")
+        }
+        Confirmation::Downgrade {
+            package,
+            installed,
+            apk,
+            ..
+        } => (
+            "Downgrade".to_string(),
+            vec![
+                Line::from(package.as_str()),
+                Line::styled(
+                    format!("- versionCode {} on the device", installed),
+                    Style::default().fg(Color::Red),
+                ),
+                Line::styled(
+                    format!("+ versionCode {} in the release", apk),
+                    Style::default().fg(Color::Green),
+                ),
+                Line::from("Install the older version anyway (pm install -d)?"),
+            ],
+            "downgrade",
+        ),
+        Confirmation::DeleteCached { tag_name, .. } => (
+            "Delete cached APK".to_string(),
+            vec![Line::from(format!(
+                "Delete the cached APK of {}?",
+                tag_name
+            ))],
+            "delete",
+        ),
+        Confirmation::ClearCache => (
+            "Clear cache".to_string(),
+            vec![Line::from(
+                "Delete all cached APKs of the listed repositories?",
+            )],
+            "clear",
+        ),
+    };
+
+    // room for the listed devices, assets or releases of a batch
+    let height = if question.len() > 3 { 50 } else { 20 };
+    let popup_area = centered_rect(area, 60, height);
+    let mut text = question;
+    text.push(Line::from(""));
+    text.push(Line::from(vec![
+        Span::styled("y", Style::default().fg(Color::LightBlue)),
+        format!(" to {}, any other key to cancel", action).into(),
+    ]));
+
+    Clear.render(popup_area, buf);
+    Paragraph::new(text)
+        .block(
+            Block::bordered()
+                .title(Title::from(title).alignment(Alignment::Center))
+                .border_type(BorderType::Rounded),
+        )
+        .centered()
+        .render(popup_area, buf);
+}
+
+/// Warning that the release is old, e.g. `v1.2.0 is 6 months old, the newest is v2.0.0`.
+fn stale_line(tag_name: &str, stale: &Stale) -> Line<'static> {
+    let days = stale.age.num_days();
+    let age = match days {
+        ..=60 => format!("{} days", days),
+        61..=730 => format!("{} months", days / 30),
+        _ => format!("{} years", days / 365),
+    };
+    Line::styled(
+        format!(
+            "{} is {} old, the newest is {}",
+            tag_name, age, stale.newest
+        ),
+        Style::default().fg(Color::Yellow),
+    )
+}
+
+/// Lines listing what a batch operation works on, the first few and how many more there are.
+fn batch_lines(entries: impl ExactSizeIterator<Item = String>) -> Vec<Line<'static>> {
+    const SHOWN: usize = 8;
+    let count = entries.len();
+    let mut lines: Vec<Line> = entries
+        .take(SHOWN)
+        .map(|entry| Line::styled(entry, Style::default().fg(Color::Gray)))
+        .collect();
+    if count > SHOWN {
+        lines.push(Line::styled(
+            format!("… and {} more", count - SHOWN),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    lines
+}
+
+/// Frame of the busy indicator for the tick.
+fn spinner(tick: usize) -> &'static str {
+    const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    FRAMES[tick % FRAMES.len()]
+}
+
+/// SDK levels of the APK, once known, and of the device, red if the device is too old.
+fn sdk_line(apk: Option<(Option<u32>, Option<u32>)>, device: Option<u32>) -> Line<'static> {
+    let level = |sdk: Option<u32>| sdk.map_or_else(|| "?".to_string(), |sdk| sdk.to_string());
+    let mut spans = vec![Span::styled("SDK:      ", Style::default().fg(Color::Gray))];
+    if let Some((min, target)) = apk {
+        spans.push(format!("min {} · target {} · ", level(min), level(target)).into());
+    }
+    let too_old = matches!((apk, device), (Some((Some(min), _)), Some(device)) if device < min);
+    spans.push(Span::styled(
+        format!("device {}", level(device)),
+        if too_old {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        },
+    ));
+    Line::from(spans)
+}
+
+/// Byte ranges of the rows `line` takes when wrapped at spaces to `width` columns.
+fn wrap_ranges(line: &str, width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while line[start..].chars().count() > width {
+        let rest = &line[start..];
+        let hard = rest
+            .char_indices()
+            .nth(width)
+            .map_or(rest.len(), |(i, _)| i);
+        let end = match rest[..hard].rfind(' ') {
+            Some(space) if space > 0 => space + 1,
+            _ => hard,
+        };
+        ranges.push((start, start + end));
+        start += end;
+    }
+    ranges.push((start, line.len()));
+    ranges
+}
+
+/// Notes wrapped to `width` with the case-insensitive matches of `query` highlighted, the one at
+/// index `current` standing out, and the row each match starts in.
+fn highlight_matches(
+    body: &str,
+    query: &str,
+    width: usize,
+    current: usize,
+) -> (Vec<Line<'static>>, Vec<usize>) {
+    let query = query.to_ascii_lowercase();
+    let mut lines = Vec::new();
+    let mut rows = Vec::new();
+    for line in body.lines() {
+        // ASCII lowercasing keeps the byte offsets valid for the original line
+        let matches: Vec<(usize, usize)> = line
+            .to_ascii_lowercase()
+            .match_indices(&query)
+            .map(|(start, found)| (start, start + found.len()))
+            .collect();
+        // index of the first match of this line among all matches
+        let first = rows.len();
+        for (start, end) in wrap_ranges(line, width) {
+            let mut spans = Vec::new();
+            let mut cursor = start;
+            for (index, &(match_start, match_end)) in matches.iter().enumerate() {
+                if match_end <= start || match_start >= end {
+                    continue;
+                }
+                if match_start >= start {
+                    rows.push(lines.len());
+                }
+                let from = match_start.max(start);
+                let to = match_end.min(end);
+                spans.push(Span::raw(line[cursor..from].to_string()));
+                let style = if first + index == current {
+                    Style::default().fg(Color::Black).bg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::Black).bg(Color::Gray)
+                };
+                spans.push(Span::styled(line[from..to].to_string(), style));
+                cursor = to;
+            }
+            spans.push(Span::raw(line[cursor..end].to_string()));
+            lines.push(Line::from(spans));
+        }
+    }
+    (lines, rows)
+}
+
+/// Removes ANSI escape sequences and control characters, which would otherwise reach the terminal
+/// and garble the display, keeping line breaks and turning tabs into spaces.
+fn sanitize(text: &str) -> String {
+    let mut sanitized = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => sanitized.push('\n'),
+            '\t' => sanitized.push_str("    "),
+            '\u{1b}' => match chars.next() {
+                // CSI, e.g. colors: parameters up to a final byte in `@`..=`~`
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC, e.g. window titles or links: up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // two character sequences like ESC c
+                _ => {}
+            },
+            c if c.is_control() => {}
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+/// Line-level diff of two release notes with additions and removals colored.
+fn diff_lines(old: &str, new: &str) -> Vec<Line<'static>> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| {
+            let line = change.value().trim_end();
+            match change.tag() {
+                ChangeTag::Insert => {
+                    Line::styled(format!("+ {}", line), Style::default().fg(Color::Green))
+                }
+                ChangeTag::Delete => {
+                    Line::styled(format!("- {}", line), Style::default().fg(Color::Red))
+                }
+                ChangeTag::Equal => Line::from(format!("  {}", line)),
+            }
+        })
+        .collect()
+}
+
+/// Formats a byte count like `12.3 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Returns a rectangle of the given size in percent centered in `area`.
+fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(area);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    const LONG_TAG: &str = "release-2024-06-01-nightly-build-1234";
+    /// Symbols of the borders, which the text around must not overwrite.
+    const BORDERS: [&str; 6] = ["│", "─", "┌", "┐", "└", "┘"];
+
+    fn release(id: i64, tag_name: &str, body: &str) -> Release {
+        serde_json::from_value(json!({ "id": id, "tag_name": tag_name, "body": body })).unwrap()
+    }
+
+    fn app(releases: Vec<Release>, view: ViewMode) -> App {
+        let mut config = Config::defaults();
+        config.view = view;
+        let repo: Repo = "owner/repo".parse().unwrap();
+        let sources = vec![Source {
+            github: GithubClient::new(&config.for_repo(&repo)),
+            repo,
+            more: None,
+        }];
+        let releases: Vec<(usize, Release)> =
+            releases.into_iter().map(|release| (0, release)).collect();
+        let (_, notices_rx) = mpsc::unbounded_channel();
+        let mut app = App::new(config, sources, &releases, notices_rx);
+        app.items.state.select(Some(0));
+        app
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (buf.area.left()..buf.area.right())
+            .map(|x| buf.get(x, y).symbol())
+            .collect()
+    }
+
+    /// Asserts the block filling the buffer is drawn all the way round, but for the titles on the
+    /// top and bottom row.
+    fn assert_borders(buf: &Buffer) {
+        let area = buf.area;
+        let (right, bottom) = (area.right() - 1, area.bottom() - 1);
+        assert_eq!(buf.get(area.left(), area.top()).symbol(), "┌");
+        assert_eq!(buf.get(right, area.top()).symbol(), "┐");
+        assert_eq!(buf.get(area.left(), bottom).symbol(), "└");
+        assert_eq!(buf.get(right, bottom).symbol(), "┘");
+        for y in area.top() + 1..bottom {
+            assert_eq!(buf.get(area.left(), y).symbol(), "│", "{}", row(buf, y));
+            assert_eq!(buf.get(right, y).symbol(), "│", "{}", row(buf, y));
+        }
+    }
+
+    /// Where the borders are drawn on the whole screen.
+    fn border_cells(buf: &Buffer) -> Vec<(u16, u16)> {
+        let area = buf.area;
+        (area.top()..area.bottom())
+            .flat_map(|y| (area.left()..area.right()).map(move |x| (x, y)))
+            .filter(|&(x, y)| BORDERS.contains(&buf.get(x, y).symbol()))
+            .collect()
+    }
+
+    #[test]
+    fn long_tags_are_truncated_inside_the_list() {
+        for view in [ViewMode::Compact, ViewMode::Detailed] {
+            let mut app = app(vec![release(1, LONG_TAG, ""), release(2, "v1", "")], view);
+            let mut buf = Buffer::empty(Rect::new(0, 0, 24, 6));
+
+            app.render_releases(buf.area, &mut buf);
+
+            assert_borders(&buf);
+            assert!(row(&buf, 1).contains('…'), "{}", row(&buf, 1));
+            assert!(row(&buf, 2).contains("v1"), "{}", row(&buf, 2));
         }
     }
+
+    #[test]
+    fn long_notes_wrap_inside_the_info() {
+        let url = format!("https://example.com/{}/end", "a".repeat(120));
+        let mut app = app(
+            vec![release(1, "v1", &format!("See {}", url))],
+            ViewMode::Compact,
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 10));
+
+        app.render_info(buf.area, &mut buf);
+
+        assert_borders(&buf);
+        let inside: String = (1..buf.area.bottom() - 1)
+            .map(|y| row(&buf, y).trim_matches('│').to_string())
+            .collect();
+        assert!(inside.contains("/end"), "{}", inside);
+    }
+
+    #[test]
+    fn long_tags_and_notes_keep_the_layout() {
+        let draw = |releases| {
+            let mut app = app(releases, ViewMode::Detailed);
+            let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+            terminal
+                .draw(|frame| frame.render_widget(&mut app, frame.size()))
+                .unwrap();
+            border_cells(terminal.backend().buffer())
+        };
+        let long_body = format!("https://example.com/{}", "a".repeat(500));
+
+        let short = draw(vec![release(1, "v1", "Fixes")]);
+        let long = draw(vec![release(1, LONG_TAG, &long_body)]);
+
+        assert_eq!(short, long);
+    }
+
+    /// The release with the id, and whether it is selected.
+    fn find(app: &App, id: i64) -> (&ReleaseItem, bool) {
+        let i = app
+            .items
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .unwrap();
+        (&app.items.items[i], app.items.selected() == Some(i))
+    }
+
+    #[test]
+    fn releases_sharing_a_tag_keep_their_status_and_selection() {
+        // the tag was deleted and created again for release 2
+        let mut app = app(
+            vec![release(2, "v1", "Re-created"), release(1, "v1", "First")],
+            ViewMode::Compact,
+        );
+        let first = app
+            .items
+            .items
+            .iter()
+            .position(|item| item.id == 1)
+            .unwrap();
+        app.items.items[first].status = Status::Installed;
+        app.items.select_release(0, 1);
+
+        // listed the other way round after refreshing
+        let releases = vec![
+            (0, release(1, "v1", "First")),
+            (0, release(2, "v1", "Re-created")),
+        ];
+        app.update_refresh(Ok((releases, vec![None])));
+
+        let (first, selected) = find(&app, 1);
+        assert!(matches!(first.status, Status::Installed));
+        assert!(selected);
+        let (second, selected) = find(&app, 2);
+        assert!(matches!(second.status, Status::Open));
+        assert!(!selected);
+    }
+
+    #[test]
+    fn releases_sharing_a_tag_are_queued_apart() {
+        let mut app = app(
+            vec![release(2, "v1", "Re-created"), release(1, "v1", "First")],
+            ViewMode::Compact,
+        );
+        app.items.select_release(0, 1);
+
+        app.toggle_queued();
+
+        assert_eq!(app.queue_position(find(&app, 1).0), Some(1));
+        assert_eq!(app.queue_position(find(&app, 2).0), None);
+    }
+
+    #[test]
+    fn sanitize_removes_ansi_escape_sequences() {
+        // colors, a window title ended by BEL and a link ended by ESC \
+        let body = "\u{1b}[1;31mred\u{1b}[0m \u{1b}]0;title\u{7}\u{1b}]8;;https://x.y\u{1b}\\link";
+        assert_eq!(sanitize(body), "red link");
+        // a reset of the terminal and an unterminated sequence at the end
+        assert_eq!(sanitize("\u{1b}cclear\u{1b}[31"), "clear");
+    }
+
+    #[test]
+    fn sanitize_removes_control_characters() {
+        let body = "bell\u{7} back\u{8}space\r\nnext\u{0}\u{7f}\u{9b}31m";
+        assert_eq!(sanitize(body), "bell backspace\nnext31m");
+        assert_eq!(sanitize("a\tb"), "a    b");
+    }
+
+    #[test]
+    fn sanitize_keeps_text_decoded_from_invalid_utf8() {
+        let body = String::from_utf8_lossy(b"caf\xe9 \xff\xfe \xe2\x9c\x93");
+        assert_eq!(sanitize(&body), "caf\u{fffd} \u{fffd}\u{fffd} \u{2713}");
+    }
+
+    #[test]
+    fn escape_sequences_do_not_reach_the_terminal() {
+        let mut app = app(
+            vec![release(1, "v1", "\u{1b}[2J\u{1b}]0;pwned\u{7}Notes")],
+            ViewMode::Compact,
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 6));
+
+        app.render_info(buf.area, &mut buf);
+
+        let text: String = (0..buf.area.bottom()).map(|y| row(&buf, y)).collect();
+        assert!(text.contains("Notes"), "{}", text);
+        assert!(
+            !text.contains('\u{1b}') && !text.contains("pwned"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn undo_waits_for_the_install() {
+        let mut app = app(vec![release(1, "v1", "")], ViewMode::Compact);
+
+        app.flip_status();
+        app.undo();
+
+        assert!(matches!(app.items.items[0].status, Status::Installed));
+        assert_eq!(app.history.len(), 1);
+
+        app.items.in_progress = None;
+        app.undo();
+
+        assert!(matches!(app.items.items[0].status, Status::Open));
+        assert!(app.history.is_empty());
+    }
+
+    fn release_with_assets(id: i64, tag_name: &str, assets: &[&str]) -> Release {
+        let assets: Vec<_> = (0..)
+            .zip(assets)
+            .map(|(asset_id, name)| json!({ "id": id * 10 + asset_id, "name": name }))
+            .collect();
+        serde_json::from_value(json!({ "id": id, "tag_name": tag_name, "assets": assets })).unwrap()
+    }
+
+    #[test]
+    fn device_picker_keeps_the_selected_device_when_listed_again() {
+        let device = |serial: &str| Device {
+            serial: serial.to_string(),
+            state: "device".to_string(),
+            model: None,
+        };
+        let mut app = app(vec![release(1, "v1", "")], ViewMode::Compact);
+        app.device_picker = Some(DevicePicker {
+            devices: vec![device("a"), device("b")],
+            state: ListState::default().with_selected(Some(1)),
+            refreshed: None,
+            listing: true,
+            error: None,
+            pending: None,
+        });
+
+        app.update_device_picker(Ok(vec![device("c"), device("a"), device("b")]));
+
+        let picker = app.device_picker.as_ref().unwrap();
+        assert!(!picker.listing);
+        assert_eq!(picker.state.selected(), Some(2));
+    }
+
+    #[test]
+    fn installs_the_pending_release_once_switched_to_the_picked_device() {
+        let mut app = app(vec![release(1, "v1", "")], ViewMode::Compact);
+        let device = Device {
+            serial: "a".to_string(),
+            state: "device".to_string(),
+            model: None,
+        };
+
+        app.update_task(TaskEvent::Switched {
+            device,
+            warnings: vec!["Could not remove tcp:8080!".to_string()],
+            sdk: Ok(Some(34)),
+            pending: Some(0),
+        });
+
+        assert_eq!(app.device.as_ref().map(|d| d.serial.as_str()), Some("a"));
+        assert_eq!(app.device_sdk, Some(34));
+        assert_eq!(app.items.in_progress, Some(0));
+    }
+
+    #[test]
+    fn redraws_after_moving_the_selection() {
+        let releases = vec![release(2, "v2", ""), release(1, "v1", "")];
+        let mut app = app(releases, ViewMode::Compact);
+        app.needs_redraw = false;
+
+        app.go_bottom();
+
+        assert!(app.needs_redraw);
+    }
+
+    #[test]
+    fn asset_picker_stays_with_its_release() {
+        let releases = vec![
+            release_with_assets(2, "v2", &[]),
+            release_with_assets(1, "v1", &["app-arm64.apk", "app-x86.apk"]),
+        ];
+        let mut app = app(releases, ViewMode::Compact);
+        app.items.select_release(0, 1);
+        app.open_asset_picker();
+        // the selection moves to a release without assets while the picker is open
+        app.items.select_release(0, 2);
+
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        app.handle_asset_picker_key(&down);
+        app.handle_asset_picker_key(&down);
+
+        let picker = app.asset_picker.as_ref().unwrap();
+        assert_eq!(picker.id, 1);
+        assert_eq!(picker.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn asset_picker_closes_once_its_release_has_no_assets() {
+        let mut app = app(
+            vec![release_with_assets(1, "v1", &["app.apk"])],
+            ViewMode::Compact,
+        );
+        app.open_asset_picker();
+
+        app.update_refresh(Ok((
+            vec![(0, release_with_assets(1, "v1", &[]))],
+            vec![None],
+        )));
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        app.handle_asset_picker_key(&up);
+
+        assert!(app.asset_picker.is_none());
+    }
 }
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Result};
+
+use crate::config;
+
+/// Releases pinned to the top of the list, saved to [`config::pins_path`].
+#[derive(Serialize, Deserialize, Default)]
+#[serde(transparent)]
+pub struct Pins {
+    /// Release ids by `<host>/<owner>/<repo>`.
+    repos: HashMap<String, BTreeSet<i64>>,
+}
+
+impl Pins {
+    /// Reads the saved pins, none if nothing was pinned yet.
+    pub fn load() -> Result<Self> {
+        let Some(path) = config::pins_path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn contains(&self, repo: &str, id: i64) -> bool {
+        self.repos.get(repo).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Pins the release or unpins it if it is pinned, returns whether it is pinned now.
+    pub fn toggle(&mut self, repo: &str, id: i64) -> bool {
+        let ids = self.repos.entry(repo.to_string()).or_default();
+        let pinned = !ids.remove(&id);
+        if pinned {
+            ids.insert(id);
+        }
+        if ids.is_empty() {
+            self.repos.remove(repo);
+        }
+        pinned
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = config::pins_path().ok_or_else(|| io::Error::other("No config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Result};
+use std::path::{Path, PathBuf};
+
+/// An install performed during the session.
+#[derive(Serialize)]
+pub struct InstallRecord {
+    pub time: DateTime<Utc>,
+    /// `owner/repo`
+    pub repo: String,
+    pub tag: String,
+    pub asset: String,
+    /// SHA-256 of the installed APK, unknown if it could not be downloaded.
+    pub sha256: Option<String>,
+    pub serial: String,
+    /// `installed`, or the error the install failed with.
+    pub result: String,
+}
+
+/// Hex encoded SHA-256 of the file.
+pub fn sha256(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where the report is written without `--report`.
+pub fn default_path() -> PathBuf {
+    dirs::download_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(format!(
+            "install-report-{}.csv",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+}
+
+/// Writes the records as JSON if the path ends with `.json`, else as CSV.
+pub fn write(path: &Path, records: &[InstallRecord]) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    let content = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::to_string_pretty(records)?
+    } else {
+        csv(records)
+    };
+    fs::write(path, content)
+}
+
+fn csv(records: &[InstallRecord]) -> String {
+    let mut csv = "time,repo,tag,asset,sha256,serial,result\n".to_string();
+    for record in records {
+        let fields = [
+            record.time.to_rfc3339(),
+            record.repo.clone(),
+            record.tag.clone(),
+            record.asset.clone(),
+            record.sha256.clone().unwrap_or_default(),
+            record.serial.clone(),
+            record.result.clone(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+        csv += &fields.join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quotes fields containing separators, quotes or line breaks (RFC 4180).
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Which release `--install` and the jump prompt mean: a tag like `v1.2.0`, the newest release
+/// whose tag starts with a prefix like `release-2.*`, or the one built from a commit or branch
+/// like `@abc123f`.
+pub enum Selector<'a> {
+    Tag(&'a str),
+    Prefix(&'a str),
+    Commit(&'a str),
+}
+
+/// Shortest abbreviated SHA accepted, like `git rev-parse`.
+const MIN_SHA_LENGTH: usize = 4;
+
+impl<'a> Selector<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        if let Some(commit) = input.strip_prefix('@') {
+            Selector::Commit(commit)
+        } else if let Some(prefix) = input.strip_suffix('*') {
+            Selector::Prefix(prefix)
+        } else {
+            Selector::Tag(input)
+        }
+    }
+
+    /// Whether the release with the tag, created from `target_commitish`, is meant. A commit
+    /// matches a SHA it abbreviates or the name of a branch.
+    pub fn matches(&self, tag_name: &str, target_commitish: &str) -> bool {
+        match self {
+            Selector::Tag(tag) => tag_name == *tag,
+            Selector::Prefix(prefix) => tag_name.starts_with(prefix),
+            Selector::Commit(commit) => {
+                target_commitish == *commit
+                    || (commit.len() >= MIN_SHA_LENGTH
+                        && commit.chars().all(|c| c.is_ascii_hexdigit())
+                        && target_commitish.len() >= commit.len()
+                        && target_commitish[..commit.len()].eq_ignore_ascii_case(commit))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Selector<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::Tag(tag) => write!(f, "tagged '{}'", tag),
+            Selector::Prefix(prefix) => write!(f, "with a tag starting with '{}'", prefix),
+            Selector::Commit(commit) => write!(f, "built from commit or branch '{}'", commit),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::backend::Backend;
+use ratatui::layout::Alignment;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Wrap};
+use ratatui::Terminal;
+use std::io::Result;
+
+use crate::config::{self, Config};
+use crate::github::GithubClient;
+use crate::{centered_rect, spinner, TICK_RATE};
+
+/// How the first-run setup ended.
+pub enum Outcome {
+    /// A token GitHub accepted.
+    Token(String),
+    Quit,
+}
+
+enum Step {
+    Input,
+    Failed(String),
+    /// Verified for this login, asking whether to save the token.
+    Save(String),
+}
+
+/// Asks for a token when none is configured, verifies it against `/user` and offers to save it.
+pub async fn run(terminal: &mut Terminal<impl Backend>, config: &Config) -> Result<Outcome> {
+    let mut input = String::new();
+    let mut step = Step::Input;
+    loop {
+        terminal.draw(|f| {
+            let area = centered_rect(f.size(), 70, 50);
+            f.render_widget(Clear, area);
+            f.render_widget(prompt(config, &input, &step, None), area);
+        })?;
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if let Step::Save(_) = step {
+            match key.code {
+                KeyCode::Char('y') => {
+                    if let Err(error) = config::save_token(&input) {
+                        step = Step::Failed(format!("Could not save the token! {}", error));
+                        continue;
+                    }
+                    return Ok(Outcome::Token(input));
+                }
+                KeyCode::Char('n') | KeyCode::Esc => return Ok(Outcome::Token(input)),
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Esc => return Ok(Outcome::Quit),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter if !input.trim().is_empty() => {
+                input = input.trim().to_string();
+                step = match verify(terminal, config, &input).await? {
+                    Ok(login) => Step::Save(login),
+                    Err(error) => Step::Failed(error),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Asks GitHub who the token belongs to, redrawing the spinner meanwhile.
+async fn verify(
+    terminal: &mut Terminal<impl Backend>,
+    config: &Config,
+    token: &str,
+) -> Result<std::result::Result<String, String>> {
+    let mut github = GithubClient::new(config);
+    github.set_token(token.to_string());
+    let user = github.user();
+    tokio::pin!(user);
+
+    let mut ticker = tokio::time::interval(TICK_RATE);
+    let mut tick = 0;
+    loop {
+        tokio::select! {
+            result = &mut user => {
+                return Ok(result.map_err(|error| {
+                    if error.is_unauthorized() {
+                        "GitHub did not accept the token.".to_string()
+                    } else {
+                        format!("Could not verify the token! {}", error)
+                    }
+                }));
+            }
+            _ = ticker.tick() => {
+                tick += 1;
+                terminal.draw(|f| {
+                    let area = centered_rect(f.size(), 70, 50);
+                    f.render_widget(Clear, area);
+                    f.render_widget(prompt(config, token, &Step::Input, Some(tick)), area);
+                })?;
+            }
+        }
+    }
+}
+
+fn prompt<'a>(
+    config: &Config,
+    input: &str,
+    step: &Step,
+    verifying: Option<usize>,
+) -> Paragraph<'a> {
+    let key = |label: &'a str| Span::styled(label, Style::default().fg(Color::LightBlue));
+    let mut text = vec![
+        Line::from("No GitHub token found."),
+        Line::from(""),
+        Line::from(format!(
+            "Create one at https://{}/settings/tokens with read access to the releases",
+            config.host
+        )),
+        Line::from("(classic: no scope for public repositories, `repo` for private ones)."),
+        Line::from("Paste it here, or set $GH_ACCESS_TOKEN next time."),
+        Line::from(""),
+        Line::from("•".repeat(input.chars().count())),
+        Line::from(""),
+    ];
+    text.push(match (step, verifying) {
+        (_, Some(tick)) => Line::from(format!("{} Verifying…", spinner(tick))),
+        (Step::Failed(error), _) => Line::styled(error.clone(), Style::default().fg(Color::Red)),
+        (Step::Save(login), _) => Line::styled(
+            format!("Signed in as {}.", login),
+            Style::default().fg(Color::Green),
+        ),
+        (Step::Input, _) => Line::from(""),
+    });
+    text.push(Line::from(""));
+    text.push(match step {
+        Step::Save(_) => {
+            let path = config::token_path().unwrap_or_default();
+            Line::from(vec![
+                format!("Save it to {}? ", path.display()).into(),
+                key("y"),
+                " / ".into(),
+                key("n"),
+                " to use it for this session only".into(),
+            ])
+        }
+        _ => Line::from(vec![
+            key("Enter"),
+            " to verify, ".into(),
+            key("Esc"),
+            " to quit".into(),
+        ]),
+    });
+
+    Paragraph::new(text)
+        .block(
+            Block::bordered()
+                .title(Title::from("Setup").alignment(Alignment::Center))
+                .border_type(BorderType::Rounded),
+        )
+        .centered()
+        .wrap(Wrap { trim: false })
+}
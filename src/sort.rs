@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::io::{self, Result};
+use std::str::FromStr;
+
+use crate::config;
+
+/// What the release list is sorted by.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// The order GitHub returns the releases in, by creation.
+    #[default]
+    Created,
+    /// The version in the tag, e.g. `v1.10.0` after `v1.9.2`.
+    Semver,
+    Published,
+    /// Downloads of all assets of a release.
+    Downloads,
+}
+
+impl FromStr for SortKey {
+    type Err = ();
+
+    fn from_str(key: &str) -> std::result::Result<Self, ()> {
+        match key {
+            "created" => Ok(SortKey::Created),
+            "semver" => Ok(SortKey::Semver),
+            "published" => Ok(SortKey::Published),
+            "downloads" => Ok(SortKey::Downloads),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Desc,
+    Asc,
+}
+
+impl FromStr for SortOrder {
+    type Err = ();
+
+    fn from_str(order: &str) -> std::result::Result<Self, ()> {
+        match order {
+            "desc" => Ok(SortOrder::Desc),
+            "asc" => Ok(SortOrder::Asc),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Order of the release list, saved to [`config::sort_path`] when changed in the TUI.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug)]
+pub struct Sort {
+    pub key: SortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+impl Sort {
+    /// The following mode: each key descending, then ascending, then the next key.
+    pub fn next(self) -> Self {
+        match self.order {
+            SortOrder::Desc => Self {
+                order: SortOrder::Asc,
+                ..self
+            },
+            SortOrder::Asc => Self {
+                key: match self.key {
+                    SortKey::Created => SortKey::Semver,
+                    SortKey::Semver => SortKey::Published,
+                    SortKey::Published => SortKey::Downloads,
+                    SortKey::Downloads => SortKey::Created,
+                },
+                order: SortOrder::Desc,
+            },
+        }
+    }
+
+    /// Turns the descending `ordering` around if sorting ascending.
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self.order {
+            SortOrder::Desc => ordering,
+            SortOrder::Asc => ordering.reverse(),
+        }
+    }
+
+    /// Reads the sort chosen in the TUI last time, if any.
+    pub fn load_saved() -> Result<Option<Self>> {
+        let Some(path) = config::sort_path() else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = config::sort_path().ok_or_else(|| io::Error::other("No config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self.key {
+            SortKey::Created => "created",
+            SortKey::Semver => "semver",
+            SortKey::Published => "published",
+            SortKey::Downloads => "downloads",
+        };
+        let arrow = match self.order {
+            SortOrder::Desc => "↓",
+            SortOrder::Asc => "↑",
+        };
+        write!(f, "{} {}", key, arrow)
+    }
+}
+
+/// Compares the versions in two tags like `v1.10.0` and `release-1.9.2-rc.1`, a release is newer
+/// than its pre-releases. Tags without a version are older than any with one.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (version(a), version(b)) {
+        (Some((a_numbers, a_pre)), Some((b_numbers, b_pre))) => a_numbers
+            .cmp(&b_numbers)
+            .then_with(|| match (a_pre, b_pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a_pre), Some(b_pre)) => compare_pre_releases(a_pre, b_pre),
+            }),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The numbers of the first version in the tag and what follows a `-` after them.
+fn version(tag: &str) -> Option<(Vec<u64>, Option<&str>)> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    let rest = &tag[start..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let numbers = rest[..end]
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().unwrap_or(u64::MAX))
+        .collect();
+    let pre = rest[end..]
+        .strip_prefix('-')
+        .map(|pre| pre.split('+').next().unwrap_or(pre))
+        .filter(|pre| !pre.is_empty());
+    Some((numbers, pre))
+}
+
+/// Compares dot separated identifiers like `rc.2` and `rc.10`, numbers numerically.
+fn compare_pre_releases(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (Some(a), Some(b)) => {
+                let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
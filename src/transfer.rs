@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
+
+use crate::github::{Asset, GithubClient};
+
+/// How many assets of a batch are downloaded at the same time.
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+/// Period the download speed is averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(3);
+
+/// State of a single asset in a batch download.
+#[derive(Clone)]
+pub enum TransferState {
+    Queued,
+    Downloading { done: u64, total: Option<u64> },
+    Verifying,
+    Done,
+    Failed(String),
+}
+
+impl TransferState {
+    /// Share of the asset that is downloaded already.
+    pub fn ratio(&self) -> f64 {
+        match self {
+            TransferState::Queued | TransferState::Failed(_) => 0.0,
+            TransferState::Downloading {
+                done,
+                total: Some(total),
+            } if *total > 0 => (*done as f64 / *total as f64).min(1.0),
+            TransferState::Downloading { .. } => 0.0,
+            TransferState::Verifying | TransferState::Done => 1.0,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            TransferState::Queued => "queued".to_string(),
+            TransferState::Downloading { .. } => {
+                format!("downloading {:.0}%", self.ratio() * 100.0)
+            }
+            TransferState::Verifying => "verifying".to_string(),
+            TransferState::Done => "done".to_string(),
+            TransferState::Failed(error) => format!("failed: {}", error),
+        }
+    }
+}
+
+/// Progress of a single download with its speed averaged over the last seconds.
+#[derive(Default)]
+pub struct Throughput {
+    pub done: u64,
+    pub total: Option<u64>,
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl Throughput {
+    pub fn record(&mut self, done: u64, total: Option<u64>) {
+        let now = Instant::now();
+        if done < self.done {
+            // a new download started
+            self.samples.clear();
+        }
+        self.done = done;
+        self.total = total;
+        self.samples.push_back((now, done));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn ratio(&self) -> f64 {
+        match self.total {
+            Some(total) if total > 0 => (self.done as f64 / total as f64).min(1.0),
+            _ => 0.0,
+        }
+    }
+
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        let (first_time, first_done) = self.samples.front()?;
+        let (last_time, last_done) = self.samples.back()?;
+        let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+        (elapsed > 0.0).then(|| (last_done - first_done) as f64 / elapsed)
+    }
+
+    /// Estimated time until the download completes at the current speed.
+    pub fn remaining(&self) -> Option<Duration> {
+        let rate = self.bytes_per_second().filter(|rate| *rate > 0.0)?;
+        let left = self.total?.saturating_sub(self.done);
+        Some(Duration::from_secs_f64(left as f64 / rate))
+    }
+}
+
+/// Progress message sent from a download task to the UI.
+pub struct TransferUpdate {
+    pub asset_id: i32,
+    pub state: TransferState,
+}
+
+/// An asset listed in the progress panel.
+pub struct Transfer {
+    pub asset_id: i32,
+    pub name: String,
+    pub state: TransferState,
+}
+
+/// Downloads the assets concurrently to their paths, reporting the progress of each over `updates`.
+pub fn download_all(
+    github: &GithubClient,
+    downloads: Vec<(Asset, PathBuf)>,
+    updates: UnboundedSender<TransferUpdate>,
+) {
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+
+    for (asset, path) in downloads {
+        let github = github.clone();
+        let permits = permits.clone();
+        let updates = updates.clone();
+
+        tokio::spawn(async move {
+            let send = |state| {
+                let _ = updates.send(TransferUpdate {
+                    asset_id: asset.id,
+                    state,
+                });
+            };
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+
+            let dir = path.parent().unwrap_or(Path::new("."));
+            if let Err(error) = tokio::fs::create_dir_all(dir).await {
+                send(TransferState::Failed(error.to_string()));
+                return;
+            }
+
+            send(TransferState::Downloading {
+                done: 0,
+                total: Some(asset.size),
+            });
+            let download = github
+                .download_asset(&asset, &path.to_string_lossy(), |done, total| {
+                    send(TransferState::Downloading { done, total })
+                })
+                .await;
+            if let Err(error) = download {
+                send(TransferState::Failed(error.to_string()));
+                return;
+            }
+
+            send(TransferState::Verifying);
+            match tokio::fs::metadata(&path).await {
+                Ok(metadata) if metadata.len() == asset.size => send(TransferState::Done),
+                Ok(metadata) => send(TransferState::Failed(format!(
+                    "expected {} bytes, got {}",
+                    asset.size,
+                    metadata.len()
+                ))),
+                Err(error) => send(TransferState::Failed(error.to_string())),
+            }
+        });
+    }
+}
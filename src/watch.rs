@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::error::AppError;
+use crate::github::GithubClient;
+
+/// Releases of a source that appeared since the last poll, newest first.
+pub struct NewReleases {
+    pub source: usize,
+    /// Ids and tags.
+    pub releases: Vec<(i64, String)>,
+}
+
+/// Polls the newest releases of a source every `period` and sends the ones whose id is not in
+/// `known`, or the error if a poll fails.
+pub fn spawn(
+    source: usize,
+    github: GithubClient,
+    period: Duration,
+    mut known: HashSet<i64>,
+    updates: UnboundedSender<Result<NewReleases, AppError>>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // the first tick completes right away, the releases were just fetched
+        ticker.tick().await;
+
+        let mut etag = None;
+        loop {
+            ticker.tick().await;
+            let (releases, new_etag) = match github.poll_releases(etag.as_deref()).await {
+                Ok(Some(page)) => page,
+                Ok(None) => continue,
+                Err(error) => {
+                    if updates.send(Err(error)).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            etag = new_etag;
+
+            let releases: Vec<(i64, String)> = releases
+                .into_iter()
+                .filter(|(id, _)| known.insert(*id))
+                .collect();
+            if !releases.is_empty() && updates.send(Ok(NewReleases { source, releases })).is_err() {
+                return;
+            }
+        }
+    });
+}